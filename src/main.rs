@@ -30,10 +30,12 @@ fn chapter_11() {
       let perlin_obj = PerlinNoise2D::new(6, 10.0, 10.0, 1.0, 2.0, (100.0, 100.0), 1.0, 97);
 
       let mut sphere = Sphere::new();
-      sphere.transform = Matrix4x4::translation(-0.5, 1.0, 0.5)
-        * Matrix4x4::rotation_z(2.5 * PI / 3.0)
-        * Matrix4x4::rotation_x(3.5 * PI / 4.0)
-        * Matrix4x4::rotation_y(0.6 * PI / 4.0);
+      sphere.transform = Transform::new(
+        Matrix4x4::translation(-0.5, 1.0, 0.5)
+          * Matrix4x4::rotation_z(2.5 * PI / 3.0)
+          * Matrix4x4::rotation_x(3.5 * PI / 4.0)
+          * Matrix4x4::rotation_y(0.6 * PI / 4.0),
+      );
       sphere.material = Material {
         pattern: Pattern::perlin(
           perlin_obj,
@@ -55,7 +57,8 @@ fn chapter_11() {
 
     let right = {
       let mut sphere = Sphere::new();
-      sphere.transform = Matrix4x4::translation(1.5, 0.5, 2.3) * Matrix4x4::scale(0.5, 0.5, 0.5);
+      sphere.transform =
+        Transform::new(Matrix4x4::translation(1.5, 0.5, 2.3) * Matrix4x4::scale(0.5, 0.5, 0.5));
       sphere.material = Material {
         pattern: Pattern::gradient((0.5, 0.0, 0.1).into(), (0.5, 0.5, 0.9).into())
           .with_transform(Matrix4x4::rotation_z(3.0 * PI / 5.0) * Matrix4x4::scale(0.5, 1.0, 1.0)),
@@ -69,9 +72,11 @@ fn chapter_11() {
 
     let left = {
       let mut sphere = Sphere::new();
-      sphere.transform = Matrix4x4::translation(-1.7, 3.1, -1.3)
-        * Matrix4x4::rotation_x(PI / 6.0)
-        * Matrix4x4::scale(0.33, 0.33, 0.33);
+      sphere.transform = Transform::new(
+        Matrix4x4::translation(-1.7, 3.1, -1.3)
+          * Matrix4x4::rotation_x(PI / 6.0)
+          * Matrix4x4::scale(0.33, 0.33, 0.33),
+      );
       sphere.material = Material {
         pattern: Pattern::ring((1.0, 0.4, 0.1).into(), (0.9, 0.1, 0.1).into())
           .with_transform(Matrix4x4::scale(0.25, 0.25, 0.25)),
@@ -85,7 +90,8 @@ fn chapter_11() {
 
     let hemisphere = {
       let mut sphere = Sphere::new();
-      sphere.transform = Matrix4x4::translation(0.8, 0.1, 4.0) * Matrix4x4::scale(0.5, 0.5, 0.5);
+      sphere.transform =
+        Transform::new(Matrix4x4::translation(0.8, 0.1, 4.0) * Matrix4x4::scale(0.5, 0.5, 0.5));
       sphere.material = Material {
         pattern: Pattern::solid((0.3, 0.4, 0.7).into()),
         diffuse: 0.1,
@@ -98,11 +104,12 @@ fn chapter_11() {
 
     let light = PointLight::new((-10.0, 10.0, -10.0), Colour::new(0.94, 0.9, 0.83));
 
-    world.objects.push(Box::new(floor));
+    world.add_object(Box::new(floor));
     for object in [middle, right, left, hemisphere] {
-      world.objects.push(Box::new(object));
+      world.add_object(Box::new(object));
     }
-    world.lights.push(light);
+    world.lights.push(Box::new(light));
+    world.build_bvh();
 
     world
   };
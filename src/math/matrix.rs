@@ -367,6 +367,7 @@ impl Matrix4x4 {
 #[rustfmt::skip]
 mod tests {
   use super::*;
+  use std::f32::consts::PI;
 
   #[test]
   fn construct_matrix2x2() {
@@ -431,15 +432,15 @@ mod tests {
   #[test]
   fn modify_matrix4x4() {
     let mut matrix: Matrix4x4 = [[0.0; 4]; 4].into();
-    matrix[1][3] = 3.14;
+    matrix[1][3] = PI;
     let _expected = [
       [0.0; 4],
-      [0.0, 0.0, 0.0, 3.14],
+      [0.0, 0.0, 0.0, PI],
       [0.0; 4],
       [0.0; 4]
     ].into();
 
-    assert!(matrix[1][3].approx_eq(3.14));
+    assert!(matrix[1][3].approx_eq(PI));
     assert!(matrix.clone().approx_eq(_expected));
     assert!(matches!(matrix, _expected));
   }
@@ -512,14 +513,14 @@ mod tests {
   #[test]
   fn multiply_point_with_identity4x4() {
     let point: Point = (1.0, 2.0, 3.0).into();
-    let result = Matrix4x4::IDENTITY * point.clone();
+    let result = Matrix4x4::IDENTITY * point;
     assert!(result.approx_eq(point));
   }
 
   #[test]
   fn multiply_vector_with_identity4x4() {
     let vector: Vector = (1.0, 2.0, 3.0).into();
-    let result = Matrix4x4::IDENTITY * vector.clone();
+    let result = Matrix4x4::IDENTITY * vector;
     assert!(result.approx_eq(vector));
   }
 
@@ -1,9 +1,11 @@
 pub mod matrix;
 pub mod point;
+pub mod random;
 pub mod transformation;
 pub mod vector;
 pub use matrix::*;
 pub use point::*;
+pub use random::*;
 pub use transformation::*;
 pub use vector::*;
 
@@ -0,0 +1,76 @@
+use super::*;
+
+/// Small, fast xorshift pseudo-random number generator
+///
+/// Not suitable for anything cryptographic, but deterministic for a given seed, which keeps renders
+/// reproducible and lets each pixel own its own generator when rendering in parallel
+#[derive(Debug, Clone)]
+pub struct Rng {
+  state: u64,
+}
+
+impl Rng {
+  pub fn new(seed: u64) -> Self {
+    // Mix the seed (splitmix64) so that neighbouring seeds give unrelated sequences, and make sure
+    // the state is never zero
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    Rng { state: z.max(1) }
+  }
+
+  /// Generator for the given pixel, so every pixel gets a reproducible sequence regardless of
+  /// render order
+  pub fn for_pixel(x: usize, y: usize) -> Self {
+    Rng::new(((y as u64) << 32) | x as u64)
+  }
+
+  /// Generator seeded from a position, so that shading the same point always draws the same
+  /// numbers.
+  pub fn for_point(point: Point) -> Self {
+    Rng::new(
+      point.x.to_bits() as u64
+        ^ (point.y.to_bits() as u64) << 21
+        ^ (point.z.to_bits() as u64) << 42,
+    )
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.state ^= self.state << 13;
+    self.state ^= self.state >> 7;
+    self.state ^= self.state << 17;
+    self.state
+  }
+
+  /// Uniformly distributed value in `[0, 1)`
+  pub fn next_f32(&mut self) -> f32 {
+    (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn same_seed_same_sequence() {
+    let mut a = Rng::new(42);
+    let mut b = Rng::new(42);
+    for _ in 0..16 {
+      assert_eq!(a.next_u64(), b.next_u64());
+    }
+  }
+
+  #[test]
+  fn floats_in_unit_interval() {
+    let mut rng = Rng::for_pixel(3, 7);
+    let mut sum = 0.0;
+    for _ in 0..1000 {
+      let value = rng.next_f32();
+      assert!((0.0..1.0).contains(&value));
+      sum += value;
+    }
+    assert!((sum / 1000.0 - 0.5).abs() < 0.05);
+  }
+}
@@ -1,4 +1,5 @@
 use super::*;
+use std::ops::Mul;
 
 impl Matrix4x4 {
   pub fn translation(x: f32, y: f32, z: f32) -> Self {
@@ -90,6 +91,100 @@ impl Matrix4x4 {
   }
 }
 
+/// A transformation matrix stored alongside its inverse and the transpose of its inverse, so they
+/// are only computed once rather than for every ray
+#[derive(Debug, Clone)]
+pub struct Transform {
+  matrix: Matrix4x4,
+  inverse: Matrix4x4,
+  inverse_transpose: Matrix4x4,
+}
+
+impl Transform {
+  pub const IDENTITY: Self = Transform {
+    matrix: Matrix4x4::IDENTITY,
+    inverse: Matrix4x4::IDENTITY,
+    inverse_transpose: Matrix4x4::IDENTITY,
+  };
+
+  /// For matrices known to be invertible, such as translations and rotations. Panics if the
+  /// matrix is not invertible; use [`Transform::try_new`] for any other matrix.
+  pub fn new(matrix: Matrix4x4) -> Self {
+    Transform::try_new(matrix).expect("transform matrix must be invertible")
+  }
+
+  /// Returns `None` if the matrix is not invertible
+  pub fn try_new(matrix: Matrix4x4) -> Option<Self> {
+    let inverse = matrix.inverse().ok()?;
+    Some(Transform {
+      inverse_transpose: inverse.transpose(),
+      inverse,
+      matrix,
+    })
+  }
+
+  #[inline]
+  pub fn matrix(&self) -> &Matrix4x4 {
+    &self.matrix
+  }
+
+  #[inline]
+  pub fn inverse(&self) -> &Matrix4x4 {
+    &self.inverse
+  }
+
+  #[inline]
+  pub fn inverse_transpose(&self) -> &Matrix4x4 {
+    &self.inverse_transpose
+  }
+
+  /// Returns the inverse transform, without having to invert anything
+  pub fn inverted(&self) -> Transform {
+    Transform {
+      inverse_transpose: self.matrix.transpose(),
+      inverse: self.matrix.clone(),
+      matrix: self.inverse.clone(),
+    }
+  }
+}
+
+/// Fails if the matrix is not invertible
+impl TryFrom<Matrix4x4> for Transform {
+  type Error = ();
+
+  fn try_from(matrix: Matrix4x4) -> Result<Transform, ()> {
+    Transform::try_new(matrix).ok_or(())
+  }
+}
+
+impl ApproxEq for Transform {
+  fn approx_eq(&self, rhs: Transform) -> bool {
+    self.matrix.approx_eq(rhs.matrix)
+  }
+}
+
+/// Composes two transforms, reusing their inverses rather than inverting the product
+impl Mul<&Transform> for &Transform {
+  type Output = Transform;
+
+  fn mul(self, rhs: &Transform) -> Transform {
+    let inverse = &rhs.inverse * &self.inverse;
+    Transform {
+      matrix: &self.matrix * &rhs.matrix,
+      inverse_transpose: inverse.transpose(),
+      inverse,
+    }
+  }
+}
+
+impl Mul<Transform> for Transform {
+  type Output = Transform;
+
+  fn mul(self, rhs: Transform) -> Transform {
+    &self * &rhs
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -371,4 +466,55 @@ mod tests {
 
     assert!(transform.approx_eq(expected));
   }
+
+  #[test]
+  fn transform_caches_inverse() {
+    let matrix = Matrix4x4::translation(1.0, 2.0, 3.0) * Matrix4x4::scale(2.0, 4.0, 8.0);
+    let transform = Transform::new(matrix.clone());
+
+    assert!(transform.matrix().approx_eq(matrix.clone()));
+    assert!(transform.inverse().approx_eq(matrix.inverse().unwrap()));
+    assert!(transform
+      .inverse_transpose()
+      .approx_eq(matrix.inverse().unwrap().transpose()));
+  }
+
+  #[test]
+  fn compose_transforms() {
+    let a = Matrix4x4::rotation_x(PI / 3.0) * Matrix4x4::translation(1.0, -2.0, 0.5);
+    let b = Matrix4x4::scale(2.0, 3.0, 0.5) * Matrix4x4::rotation_z(PI / 5.0);
+    let composed = Transform::new(a.clone()) * Transform::new(b.clone());
+    let expected = Transform::new(a * b);
+
+    assert!(composed.matrix().approx_eq(expected.matrix().clone()));
+    assert!(composed.inverse().approx_eq(expected.inverse().clone()));
+    assert!(composed
+      .inverse_transpose()
+      .approx_eq(expected.inverse_transpose().clone()));
+  }
+
+  #[test]
+  fn invert_transform() {
+    let transform = Transform::new(Matrix4x4::translation(1.0, 2.0, 3.0));
+    let inverted = transform.inverted();
+
+    assert!(inverted
+      .matrix()
+      .approx_eq(Matrix4x4::translation(-1.0, -2.0, -3.0)));
+    assert!(inverted
+      .inverse()
+      .approx_eq(Matrix4x4::translation(1.0, 2.0, 3.0)));
+  }
+
+  #[test]
+  fn singular_matrix_is_not_a_transform() {
+    let singular = Matrix4x4::scale(1.0, 0.0, 1.0);
+    assert!(Transform::try_new(singular.clone()).is_none());
+    assert!(Transform::try_from(singular).is_err());
+
+    let transform = Transform::try_from(Matrix4x4::scale(1.0, 2.0, 1.0)).unwrap();
+    assert!(transform
+      .inverse()
+      .approx_eq(Matrix4x4::scale(1.0, 0.5, 1.0)));
+  }
 }
@@ -0,0 +1,306 @@
+use crate::*;
+use rayon::prelude::*;
+use std::ops::{Add, Mul};
+
+/// The beauty render together with arbitrary output variables (AOVs), for compositing and as
+/// guides for denoisers. Every buffer has the camera's dimensions.
+///
+/// The passes break down the Whitted integrator, so they ignore the camera's integrator and the
+/// beauty image only matches [`Camera::render`] with [`Whitted`].
+pub struct RenderPasses {
+  /// The final image, the sum of the direct, reflection and refraction contributions
+  pub beauty: Canvas,
+  /// Unlit surface colour of the first hit, or the background for misses
+  pub albedo: Canvas,
+  /// World space surface normal of the first hit, facing the camera, with components in
+  /// `[-1, 1]`; black for misses
+  pub normal: Canvas,
+  /// Distance from the camera to the first hit in every channel, averaged over the samples that
+  /// hit an object; infinite where every sample misses
+  pub depth: Canvas,
+  /// Surface lit by every light, including ambient and shadows but without reflection or
+  /// refraction
+  pub direct: Canvas,
+  /// Light reflected off the surface of the first hit
+  pub reflection: Canvas,
+  /// Fraction of the light blocked from the first hit, averaged over the lights, in every channel
+  pub shadow: Canvas,
+}
+
+impl RenderPasses {
+  fn new(width: usize, height: usize) -> Self {
+    RenderPasses {
+      beauty: Canvas::new(width, height),
+      albedo: Canvas::new(width, height),
+      normal: Canvas::new(width, height),
+      depth: Canvas::new(width, height),
+      direct: Canvas::new(width, height),
+      reflection: Canvas::new(width, height),
+      shadow: Canvas::new(width, height),
+    }
+  }
+}
+
+/// The beauty colour and AOVs seen along a single camera ray, all computed from the same hit
+#[derive(Debug, Copy, Clone)]
+pub struct AovSample {
+  pub beauty: Colour,
+  pub albedo: Colour,
+  pub normal: Colour,
+  /// Distance to the hit, or zero for a miss so that sums of samples stay finite
+  pub depth: f32,
+  /// 1.0 for a hit and 0.0 for a miss; summed with `depth` to average it over the hits
+  pub coverage: f32,
+  pub direct: Colour,
+  pub reflection: Colour,
+  pub shadow: f32,
+}
+
+impl AovSample {
+  pub const ZERO: Self = AovSample {
+    beauty: Colour::BLACK,
+    albedo: Colour::BLACK,
+    normal: Colour::BLACK,
+    depth: 0.0,
+    coverage: 0.0,
+    direct: Colour::BLACK,
+    reflection: Colour::BLACK,
+    shadow: 0.0,
+  };
+
+  pub fn at(world: &World, ray: Ray) -> Self {
+    let intersections = world.intersect(ray);
+    let Some(hit) = intersections.hit() else {
+      let background = world.background.colour_at(ray.direction);
+      return AovSample {
+        beauty: background,
+        albedo: background,
+        ..AovSample::ZERO
+      };
+    };
+
+    let computations = hit.prepare_computations_with(ray, &intersections);
+    let material = computations.object.material();
+    let local_position = computations
+      .object
+      .world_to_object(computations.over_position);
+    let normal = computations.normal;
+
+    // The same terms as World::shade_hit, kept apart for the passes
+    let direct = world.direct_lighting(&computations);
+    let mut reflection = world.reflected_colour(&computations, MAX_RECURSION_DEPTH);
+    let mut refraction = world.refracted_colour(&computations, MAX_RECURSION_DEPTH);
+    if material.reflective > 0.0 && material.transparency > 0.0 {
+      let reflectance = computations.schlick();
+      reflection = reflection * reflectance;
+      refraction = refraction * (1.0 - reflectance);
+    }
+
+    let shadow = if world.lights.is_empty() {
+      0.0
+    } else {
+      let blocked = world.lights.iter().fold(0.0, |sum, light| {
+        sum + 1.0 - light.intensity_at(computations.over_position, world)
+      });
+      blocked / world.lights.len() as f32
+    };
+
+    AovSample {
+      beauty: direct + reflection + refraction,
+      albedo: material.pattern.colour_at(local_position),
+      normal: Colour::new(normal.x, normal.y, normal.z),
+      depth: hit.t * ray.direction.magnitude(),
+      coverage: 1.0,
+      direct,
+      reflection,
+      shadow,
+    }
+  }
+}
+
+impl Add for AovSample {
+  type Output = AovSample;
+
+  fn add(self, rhs: AovSample) -> AovSample {
+    AovSample {
+      beauty: self.beauty + rhs.beauty,
+      albedo: self.albedo + rhs.albedo,
+      normal: self.normal + rhs.normal,
+      depth: self.depth + rhs.depth,
+      coverage: self.coverage + rhs.coverage,
+      direct: self.direct + rhs.direct,
+      reflection: self.reflection + rhs.reflection,
+      shadow: self.shadow + rhs.shadow,
+    }
+  }
+}
+
+impl Mul<f32> for AovSample {
+  type Output = AovSample;
+
+  fn mul(self, scalar: f32) -> AovSample {
+    AovSample {
+      beauty: self.beauty * scalar,
+      albedo: self.albedo * scalar,
+      normal: self.normal * scalar,
+      depth: self.depth * scalar,
+      coverage: self.coverage * scalar,
+      direct: self.direct * scalar,
+      reflection: self.reflection * scalar,
+      shadow: self.shadow * scalar,
+    }
+  }
+}
+
+impl Camera {
+  /// Renders the beauty image along with the AOVs in [`RenderPasses`], shading with the Whitted
+  /// integrator whatever the camera's integrator is
+  ///
+  /// Every pass is averaged over the same samples. Adaptive sampling takes the full set of samples
+  /// for every pixel, so that the passes line up.
+  pub fn render_passes(&self, world: &World) -> RenderPasses {
+    let pixels: Vec<AovSample> = (0..self.canvas_width * self.canvas_height)
+      .into_par_iter()
+      .map(|index| {
+        let (x, y) = (index % self.canvas_width, index / self.canvas_width);
+        let mut rng = Rng::for_pixel(x, y);
+        let offsets = self.subpixel_offsets(&mut rng);
+        let aovs = offsets.iter().fold(AovSample::ZERO, |aovs, &(dx, dy)| {
+          aovs + AovSample::at(world, self.ray_for_subpixel(x, y, dx, dy))
+        });
+        aovs * (1.0 / offsets.len() as f32)
+      })
+      .collect();
+
+    let mut passes = RenderPasses::new(self.canvas_width, self.canvas_height);
+    for (index, aovs) in pixels.into_iter().enumerate() {
+      let (x, y) = (index % self.canvas_width, index / self.canvas_width);
+      let depth = if aovs.coverage > 0.0 {
+        aovs.depth / aovs.coverage
+      } else {
+        f32::INFINITY
+      };
+      passes.beauty.write_pixel(x, y, aovs.beauty);
+      passes.albedo.write_pixel(x, y, aovs.albedo);
+      passes.normal.write_pixel(x, y, aovs.normal);
+      passes
+        .depth
+        .write_pixel(x, y, Colour::new(depth, depth, depth));
+      passes.direct.write_pixel(x, y, aovs.direct);
+      passes.reflection.write_pixel(x, y, aovs.reflection);
+      passes
+        .shadow
+        .write_pixel(x, y, Colour::new(aovs.shadow, aovs.shadow, aovs.shadow));
+    }
+    passes
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::consts::PI;
+
+  #[test]
+  fn aovs_of_miss() {
+    let mut world = World::default();
+    world.background = Background::Solid(Colour::BLUE);
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 1.0, 0.0));
+
+    let aovs = AovSample::at(&world, ray);
+    assert!(aovs.albedo.approx_eq(Colour::BLUE));
+    assert!(aovs.normal.approx_eq(Colour::BLACK));
+    assert!(aovs.coverage.approx_eq(0.0));
+    assert!(aovs.beauty.approx_eq(Colour::BLUE));
+    assert!(aovs.direct.approx_eq(Colour::BLACK));
+  }
+
+  #[test]
+  fn aovs_of_hit() {
+    let world = World::default();
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+
+    let aovs = AovSample::at(&world, ray);
+    assert!(aovs.albedo.approx_eq(Colour::new(0.8, 1.0, 0.6)));
+    assert!(aovs.normal.approx_eq(Colour::new(0.0, 0.0, -1.0)));
+    assert!(aovs.depth.approx_eq(4.0));
+    assert!(aovs.coverage.approx_eq(1.0));
+    assert!(aovs.direct.approx_eq(world.colour_at(ray)));
+    assert!(aovs.beauty.approx_eq(world.colour_at(ray)));
+    assert!(aovs.reflection.approx_eq(Colour::BLACK));
+    assert!(aovs.shadow.approx_eq(0.0));
+  }
+
+  #[test]
+  fn shadow_and_reflection_passes() {
+    let mut world = World::new();
+    world
+      .lights
+      .push(Box::new(PointLight::new((0.0, 10.0, 0.0), Colour::WHITE)));
+    let mut floor = Plane::new();
+    floor.material.reflective = 0.5;
+    let mut sphere = Sphere::new();
+    sphere.transform = Transform::new(Matrix4x4::translation(0.0, 2.0, 0.0));
+    world.add_object(Box::new(floor));
+    world.add_object(Box::new(sphere));
+
+    // Straight down onto the floor, under the sphere
+    let ray = Ray::new((0.0, 0.5, 0.0), (0.0, -1.0, 0.0));
+    let aovs = AovSample::at(&world, ray);
+    assert!(aovs.shadow.approx_eq(1.0));
+    let intersections = world.intersect(ray);
+    let computations = intersections
+      .hit()
+      .unwrap()
+      .prepare_computations_with(ray, &intersections);
+    let expected = world.reflected_colour(&computations, MAX_RECURSION_DEPTH);
+    assert!(aovs.reflection.approx_eq(expected));
+    assert!(aovs.reflection.red > 0.0);
+  }
+
+  #[test]
+  fn render_passes_match_render() {
+    let world = World::default();
+    let mut camera = Camera::new(11, 11, PI / 2.0);
+    camera.set_transform(Matrix4x4::view_transform(
+      Point::new(0.0, 0.0, -5.0),
+      Point::new(0.0, 0.0, 0.0),
+      Vector::new(0.0, 1.0, 0.0),
+    ));
+
+    let passes = camera.render_passes(&world);
+    let canvas = camera.render(&world);
+    for (x, y) in [(5, 5), (0, 0), (3, 7)] {
+      assert!(passes
+        .beauty
+        .pixel_at(x, y)
+        .approx_eq(canvas.pixel_at(x, y)));
+    }
+    assert!(passes
+      .normal
+      .pixel_at(5, 5)
+      .approx_eq(Colour::new(0.0, 0.0, -1.0)));
+    assert!(passes
+      .depth
+      .pixel_at(5, 5)
+      .approx_eq(Colour::new(4.0, 4.0, 4.0)));
+  }
+
+  #[test]
+  fn depth_is_averaged_over_hits() {
+    // Only one of the four subpixel rays, through (0.5, 0.5, -1), hits the sphere
+    let mut world = World::new();
+    let mut sphere = Sphere::new();
+    sphere.transform = Transform::new(Matrix4x4::translation(2.5, 2.5, -5.0));
+    world.add_object(Box::new(sphere));
+    let camera = Camera::new(1, 1, PI / 2.0).with_samples(4, Sampling::Grid);
+
+    let passes = camera.render_passes(&world);
+    let expected = 37.5f32.sqrt() - 1.0;
+    assert!(passes.depth.pixel_at(0, 0).red.approx_eq(expected));
+
+    let camera = Camera::new(1, 1, PI / 2.0);
+    let passes = camera.render_passes(&world);
+    assert!(passes.depth.pixel_at(0, 0).red.is_infinite());
+  }
+}
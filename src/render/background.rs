@@ -0,0 +1,237 @@
+use crate::*;
+use image::codecs::hdr::HdrDecoder;
+use image::error::{ParameterError, ParameterErrorKind};
+use image::{ColorType, DynamicImage, ImageError, ImageResult};
+use std::f32::consts::PI;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+
+/// What a ray sees when it misses every object in the world.
+#[derive(Debug, Clone)]
+pub enum Background {
+  Solid(Colour),
+  /// Vertical gradient from `bottom` (looking straight down) to `top` (looking straight up)
+  Gradient {
+    bottom: Colour,
+    top: Colour,
+  },
+  Environment(EnvironmentMap),
+}
+
+impl Default for Background {
+  fn default() -> Self {
+    Background::Solid(Colour::BLACK)
+  }
+}
+
+impl Background {
+  pub fn colour_at(&self, direction: Vector) -> Colour {
+    match self {
+      Background::Solid(colour) => *colour,
+      Background::Gradient { bottom, top } => {
+        let t = (direction.normalise().y + 1.0) / 2.0;
+        *bottom * (1.0 - t) + *top * t
+      }
+      Background::Environment(map) => map.colour_at(direction),
+    }
+  }
+}
+
+/// An equirectangular (latitude/longitude) image surrounding the world.
+///
+/// The centre of the image lies along -z, the top row is straight up (+y) and the bottom row
+/// straight down. Pixels of 8 and 16-bit images are decoded from sRGB, while float images such as
+/// Radiance and OpenEXR files are used as linear colours.
+#[derive(Debug, Clone)]
+pub struct EnvironmentMap {
+  width: usize,
+  height: usize,
+  /// Shared between clones, so that they can be recognised cheaply (see [`Self::same_image`])
+  pixels: Arc<[Colour]>,
+}
+
+impl EnvironmentMap {
+  /// Loads an image file. Radiance `.hdr` files keep their full dynamic range.
+  pub fn open(path: impl AsRef<Path>) -> ImageResult<Self> {
+    let path = path.as_ref();
+    let is_hdr = path
+      .extension()
+      .is_some_and(|extension| extension.eq_ignore_ascii_case("hdr"));
+    if !is_hdr {
+      return Self::from_image(&image::open(path)?);
+    }
+
+    // `image::open` converts Radiance files to 8 bits per channel, clamping anything brighter
+    // than white, so decode them directly instead.
+    let reader = BufReader::new(File::open(path).map_err(ImageError::IoError)?);
+    let decoder = HdrDecoder::new(reader)?;
+    let metadata = decoder.metadata();
+    let pixels = decoder
+      .read_image_hdr()?
+      .into_iter()
+      .map(|pixel| Colour::new(pixel.0[0], pixel.0[1], pixel.0[2]))
+      .collect();
+    Self::new(metadata.width as usize, metadata.height as usize, pixels)
+  }
+
+  /// Builds a map from an image, which must have at least one pixel
+  pub fn from_image(image: &DynamicImage) -> ImageResult<Self> {
+    let is_linear = matches!(image.color(), ColorType::Rgb32F | ColorType::Rgba32F);
+    let image = image.to_rgb32f();
+    let pixels = image
+      .pixels()
+      .map(|pixel| {
+        let colour = Colour::new(pixel.0[0], pixel.0[1], pixel.0[2]);
+        if is_linear {
+          colour
+        } else {
+          colour.srgb_to_linear()
+        }
+      })
+      .collect();
+    Self::new(image.width() as usize, image.height() as usize, pixels)
+  }
+
+  fn new(width: usize, height: usize, pixels: Vec<Colour>) -> ImageResult<Self> {
+    if width == 0 || height == 0 {
+      return Err(ImageError::Parameter(ParameterError::from_kind(
+        ParameterErrorKind::Generic("environment map has no pixels".to_owned()),
+      )));
+    }
+    Ok(EnvironmentMap {
+      width,
+      height,
+      pixels: pixels.into(),
+    })
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  /// Whether both maps are clones of the same loaded image, without comparing their pixels
+  pub fn same_image(&self, other: &EnvironmentMap) -> bool {
+    Arc::ptr_eq(&self.pixels, &other.pixels)
+  }
+
+  pub fn pixel(&self, x: usize, y: usize) -> Colour {
+    self.pixels[y * self.width + x]
+  }
+
+  /// Texture coordinates in `[0, 1]` of the point on the map seen in `direction`
+  pub fn uv(direction: Vector) -> (f32, f32) {
+    let direction = direction.normalise();
+    let u = 0.5 + direction.x.atan2(-direction.z) / (2.0 * PI);
+    let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+    (u, v)
+  }
+
+  /// Direction in which the point `(u, v)` of the map is seen; the inverse of [`Self::uv`]
+  pub fn direction(u: f32, v: f32) -> Vector {
+    let phi = (u - 0.5) * 2.0 * PI;
+    let theta = v * PI;
+    Vector::new(
+      theta.sin() * phi.sin(),
+      theta.cos(),
+      -theta.sin() * phi.cos(),
+    )
+  }
+
+  pub fn colour_at(&self, direction: Vector) -> Colour {
+    let (u, v) = Self::uv(direction);
+    let x = ((u * self.width as f32) as usize).min(self.width - 1);
+    let y = ((v * self.height as f32) as usize).min(self.height - 1);
+    self.pixel(x, y)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::{ImageBuffer, Rgb};
+  use std::f32::consts::FRAC_1_SQRT_2;
+
+  #[test]
+  fn default_background_is_black() {
+    let background = Background::default();
+    let result = background.colour_at(Vector::new(0.0, 0.0, 1.0));
+    assert!(result.approx_eq(Colour::BLACK));
+  }
+
+  #[test]
+  fn gradient_background() {
+    let background = Background::Gradient {
+      bottom: Colour::WHITE,
+      top: Colour::BLUE,
+    };
+    let cases = [
+      ((0.0, 1.0, 0.0), Colour::BLUE),
+      ((0.0, -2.0, 0.0), Colour::WHITE),
+      ((1.0, 0.0, 0.0), Colour::new(0.5, 0.5, 1.0)),
+    ];
+    for (direction, expected) in cases {
+      let result = background.colour_at(direction.into());
+      assert!(result.approx_eq(expected));
+    }
+  }
+
+  #[test]
+  fn environment_uv_round_trip() {
+    let cases = [
+      ((0.0, 0.0, -1.0), (0.5, 0.5)),
+      ((1.0, 0.0, 0.0), (0.75, 0.5)),
+      ((0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2), (0.5, 0.25)),
+    ];
+    for (direction, (u, v)) in cases {
+      let direction = Vector::from(direction);
+      let result = EnvironmentMap::uv(direction);
+      assert!(result.0.approx_eq(u) && result.1.approx_eq(v));
+      assert!(EnvironmentMap::direction(u, v).approx_eq(direction));
+    }
+  }
+
+  #[test]
+  fn environment_map_sampled_by_direction() {
+    // Left half red, right half blue
+    let image = ImageBuffer::from_fn(4, 2, |x, _| {
+      if x < 2 {
+        Rgb([255u8, 0, 0])
+      } else {
+        Rgb([0u8, 0, 255])
+      }
+    });
+    let map = EnvironmentMap::from_image(&DynamicImage::ImageRgb8(image)).unwrap();
+    assert_eq!((map.width(), map.height()), (4, 2));
+
+    let background = Background::Environment(map);
+    let left = background.colour_at(Vector::new(-1.0, 0.0, -0.1));
+    let right = background.colour_at(Vector::new(1.0, 0.0, -0.1));
+    assert!(left.approx_eq(Colour::RED));
+    assert!(right.approx_eq(Colour::BLUE));
+  }
+
+  #[test]
+  fn environment_map_decodes_srgb() {
+    let image = ImageBuffer::from_pixel(2, 1, Rgb([128u8, 255, 0]));
+    let map = EnvironmentMap::from_image(&DynamicImage::ImageRgb8(image)).unwrap();
+    assert!(map
+      .pixel(0, 0)
+      .approx_eq(Colour::from_srgb(128.0 / 255.0, 1.0, 0.0)));
+
+    let image = ImageBuffer::from_pixel(2, 1, Rgb([0.5f32, 2.0, 0.0]));
+    let map = EnvironmentMap::from_image(&DynamicImage::ImageRgb32F(image)).unwrap();
+    assert!(map.pixel(0, 0).approx_eq(Colour::new(0.5, 2.0, 0.0)));
+  }
+
+  #[test]
+  fn empty_environment_map_is_rejected() {
+    let image = ImageBuffer::<Rgb<u8>, _>::new(0, 0);
+    assert!(EnvironmentMap::from_image(&DynamicImage::ImageRgb8(image)).is_err());
+  }
+}
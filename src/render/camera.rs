@@ -2,12 +2,30 @@ use crate::*;
 use image::{ImageBuffer, Rgb};
 use rayon::prelude::*;
 
+/// How the samples for a pixel are placed when `samples_per_pixel > 1`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampling {
+  /// Sample the centre of each cell of a regular sub-pixel grid
+  Grid,
+  /// Sample a random position within each cell of the sub-pixel grid
+  Jittered,
+  /// Sample the pixel corners first, and only add the full jittered grid when any corner differs
+  /// from their mean by more than `threshold`
+  Adaptive { threshold: f32 },
+}
+
 pub struct Camera {
   pub canvas_width: usize,
   pub canvas_height: usize,
   pub fov: f32,
-  transform: Matrix4x4,
-  inverse_transform: Matrix4x4,
+  /// Rounded down to a square number so the samples form an `n` x `n` grid
+  pub samples_per_pixel: usize,
+  pub sampling: Sampling,
+  /// Shading for camera rays, [`Whitted`] by default
+  pub integrator: Box<dyn Integrator>,
+  /// Exposure, tone mapping and encoding used by [`Camera::render_img`]; clamped sRGB by default
+  pub tone_mapping: ToneMapping,
+  transform: Transform,
   pixel_size: f32,
   half_width: f32,
   half_height: f32,
@@ -28,23 +46,47 @@ impl Camera {
       canvas_width,
       canvas_height,
       fov,
-      transform: Matrix4x4::IDENTITY,
-      inverse_transform: Matrix4x4::IDENTITY,
+      samples_per_pixel: 1,
+      sampling: Sampling::Grid,
+      integrator: Box::new(Whitted::default()),
+      tone_mapping: ToneMapping::default(),
+      transform: Transform::IDENTITY,
       pixel_size,
       half_width,
       half_height,
     }
   }
 
-  // TODO: avoid calculating inverse transform for every ray
+  pub fn with_samples(mut self, samples_per_pixel: usize, sampling: Sampling) -> Self {
+    self.samples_per_pixel = samples_per_pixel;
+    self.sampling = sampling;
+    self
+  }
+
+  pub fn with_integrator(mut self, integrator: impl Integrator + 'static) -> Self {
+    self.integrator = Box::new(integrator);
+    self
+  }
+
+  pub fn with_tone_mapping(mut self, tone_mapping: ToneMapping) -> Self {
+    self.tone_mapping = tone_mapping;
+    self
+  }
+
   pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
-    let x_offset = (x as f32 + 0.5) * self.pixel_size;
-    let y_offset = (y as f32 + 0.5) * self.pixel_size;
+    self.ray_for_subpixel(x, y, 0.5, 0.5)
+  }
+
+  /// Ray through the point `(dx, dy)` of the pixel, where both offsets are in `[0, 1]` and
+  /// `(0.5, 0.5)` is the pixel centre
+  pub fn ray_for_subpixel(&self, x: usize, y: usize, dx: f32, dy: f32) -> Ray {
+    let x_offset = (x as f32 + dx) * self.pixel_size;
+    let y_offset = (y as f32 + dy) * self.pixel_size;
 
     let world_x = self.half_width - x_offset;
     let world_y = self.half_height - y_offset;
 
-    let inverse_transform = &self.transform.inverse().unwrap();
+    let inverse_transform = self.transform.inverse();
     let pixel = inverse_transform * Point::new(world_x, world_y, -1.0);
     let origin = inverse_transform * Point::ORIGIN;
     let direction = (pixel - origin).normalise();
@@ -52,6 +94,73 @@ impl Camera {
     Ray::new(origin, direction)
   }
 
+  /// Colour of the pixel, supersampled according to `samples_per_pixel` and `sampling`
+  pub fn colour_for_pixel(&self, world: &World, x: usize, y: usize) -> Colour {
+    let mut rng = Rng::for_pixel(x, y);
+
+    if let Sampling::Adaptive { threshold } = self.sampling {
+      if self.samples_per_pixel >= 4 {
+        let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]
+          .map(|(dx, dy)| self.trace(world, self.ray_for_subpixel(x, y, dx, dy), &mut rng));
+        let mean = corners
+          .iter()
+          .fold(Colour::BLACK, |sum, &colour| sum + colour)
+          * 0.25;
+        let uniform = corners.iter().all(|&colour| {
+          let difference = colour - mean;
+          difference.red.abs() <= threshold
+            && difference.green.abs() <= threshold
+            && difference.blue.abs() <= threshold
+        });
+        if uniform {
+          return mean;
+        }
+
+        // Refine, keeping the corners in the average
+        let offsets = self.subpixel_offsets(&mut rng);
+        let sum = offsets.iter().fold(mean * 4.0, |sum, &(dx, dy)| {
+          sum + self.trace(world, self.ray_for_subpixel(x, y, dx, dy), &mut rng)
+        });
+        return sum * (1.0 / (offsets.len() + 4) as f32);
+      }
+    }
+
+    let offsets = self.subpixel_offsets(&mut rng);
+    let sum = offsets.iter().fold(Colour::BLACK, |sum, &(dx, dy)| {
+      sum + self.trace(world, self.ray_for_subpixel(x, y, dx, dy), &mut rng)
+    });
+    sum * (1.0 / offsets.len() as f32)
+  }
+
+  /// Positions within a pixel to take samples at: one per cell of a square grid, at the cell centre
+  /// or jittered within the cell
+  pub(crate) fn subpixel_offsets(&self, rng: &mut Rng) -> Vec<(f32, f32)> {
+    let side = ((self.samples_per_pixel as f32).sqrt() as usize).max(1);
+    if side == 1 {
+      return vec![(0.5, 0.5)];
+    }
+
+    let jitter = self.sampling != Sampling::Grid;
+    let cell = 1.0 / side as f32;
+    let mut offsets = Vec::with_capacity(side * side);
+    for j in 0..side {
+      for i in 0..side {
+        let (jitter_x, jitter_y) = if jitter {
+          (rng.next_f32(), rng.next_f32())
+        } else {
+          (0.5, 0.5)
+        };
+        offsets.push(((i as f32 + jitter_x) * cell, (j as f32 + jitter_y) * cell));
+      }
+    }
+    offsets
+  }
+
+  /// Colour seen along a single camera ray
+  pub(crate) fn trace(&self, world: &World, ray: Ray, rng: &mut Rng) -> Colour {
+    self.integrator.colour_at(world, ray, rng)
+  }
+
   pub fn render_img(&self, world: &World) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
     eprintln!("rendering..");
     let capacity = 3 * self.canvas_height * self.canvas_width;
@@ -72,8 +181,8 @@ impl Camera {
           if x == 0 {
             eprint!("\r{y:6.0} ");
           }
-          let ray = self.ray_for_pixel(x, y);
-          let rgb: Rgb<u8> = world.colour_at(ray).into();
+          let colour = self.colour_for_pixel(world, x, y);
+          let rgb = self.tone_mapping.to_rgb8(colour);
           pixel[0] = rgb.0[0];
           pixel[1] = rgb.0[1];
           pixel[2] = rgb.0[2];
@@ -88,8 +197,7 @@ impl Camera {
     let mut canvas = Canvas::new(self.canvas_width, self.canvas_height);
     for y in 0..self.canvas_height {
       for x in 0..self.canvas_width {
-        let ray = self.ray_for_pixel(x, y);
-        let colour = world.colour_at(ray);
+        let colour = self.colour_for_pixel(world, x, y);
         canvas.write_pixel(x, y, colour);
       }
     }
@@ -97,16 +205,16 @@ impl Camera {
   }
 
   pub fn transform(&self) -> &Matrix4x4 {
-    &self.transform
+    self.transform.matrix()
   }
 
   pub fn inverse_transform(&self) -> &Matrix4x4 {
-    &self.inverse_transform
+    self.transform.inverse()
   }
 
+  /// Panics if the matrix is not invertible
   pub fn set_transform(&mut self, transform: Matrix4x4) {
-    self.inverse_transform = transform.inverse().unwrap();
-    self.transform = transform;
+    self.transform = Transform::new(transform);
   }
 }
 
@@ -122,7 +230,7 @@ mod tests {
     assert_eq!(camera.canvas_width, 160);
     assert_eq!(camera.canvas_height, 120);
     assert!(camera.fov.approx_eq(PI / 2.0));
-    assert!(camera.transform.approx_eq(Matrix4x4::IDENTITY));
+    assert!(camera.transform().approx_eq(Matrix4x4::IDENTITY));
   }
 
   #[test]
@@ -158,7 +266,7 @@ mod tests {
   #[test]
   fn construct_ray_transformed_camera() {
     let mut camera = Camera::new(201, 101, PI / 2.0);
-    camera.transform = Matrix4x4::rotation_y(PI / 4.0) * Matrix4x4::translation(0.0, -2.0, 5.0);
+    camera.set_transform(Matrix4x4::rotation_y(PI / 4.0) * Matrix4x4::translation(0.0, -2.0, 5.0));
 
     let ray = camera.ray_for_pixel(100, 50);
 
@@ -175,12 +283,12 @@ mod tests {
   fn render() {
     let world = World::default();
     let mut camera = Camera::new(11, 11, PI / 2.0);
-    camera.transform = {
+    camera.set_transform({
       let from = Point::new(0.0, 0.0, -5.0);
       let to = Point::new(0.0, 0.0, 0.0);
       let up = Vector::new(0.0, 1.0, 0.0);
       Matrix4x4::view_transform(from, to, up)
-    };
+    });
 
     let canvas = camera.render(&world);
     let result = canvas.pixel_at(5, 5);
@@ -188,4 +296,152 @@ mod tests {
     let expected = Colour::new(0.38063, 0.47578, 0.28547);
     assert!(result.approx_eq(expected));
   }
+
+  /// A white cube whose edge runs vertically through the middle of pixel (5, 5) of an 11x11 camera
+  /// at the origin
+  fn edge_world() -> World {
+    let mut world = World::new();
+    let mut cube = Cube::new();
+    cube.transform =
+      Transform::new(Matrix4x4::translation(-10.0, 0.0, -5.0) * Matrix4x4::scale(10.0, 10.0, 1.0));
+    cube.material.pattern = Pattern::solid(Colour::WHITE);
+    cube.material.ambient = 1.0;
+    cube.material.diffuse = 0.0;
+    cube.material.specular = 0.0;
+    world.add_object(Box::new(cube));
+    world
+      .lights
+      .push(Box::new(PointLight::new((0.0, 0.0, 0.0), Colour::WHITE)));
+    world
+  }
+
+  #[test]
+  fn default_single_sample() {
+    let camera = Camera::new(11, 11, PI / 2.0);
+    assert_eq!(camera.samples_per_pixel, 1);
+    let world = World::default();
+    let expected = world.colour_at(camera.ray_for_pixel(5, 5));
+    assert!(camera.colour_for_pixel(&world, 5, 5).approx_eq(expected));
+  }
+
+  #[test]
+  fn ray_for_subpixel_corner() {
+    let camera = Camera::new(201, 101, PI / 2.0);
+    let ray = camera.ray_for_subpixel(100, 50, 0.0, 0.0);
+    let expected = camera.ray_for_pixel(100, 50);
+    assert!(!ray.approx_eq(expected));
+    let ray = camera.ray_for_subpixel(100, 50, 0.5, 0.5);
+    assert!(ray.approx_eq(expected));
+  }
+
+  #[test]
+  fn grid_sampling_blends_edge() {
+    let world = edge_world();
+    let camera = Camera::new(11, 11, PI / 2.0).with_samples(4, Sampling::Grid);
+    let result = camera.colour_for_pixel(&world, 5, 5);
+    assert!(result.approx_eq(Colour::new(0.5, 0.5, 0.5)));
+  }
+
+  #[test]
+  fn jittered_sampling_blends_edge() {
+    let world = edge_world();
+    let camera = Camera::new(11, 11, PI / 2.0).with_samples(16, Sampling::Jittered);
+    let result = camera.colour_for_pixel(&world, 5, 5);
+    assert!(result.approx_eq(Colour::new(0.5, 0.5, 0.5)));
+    // Jitter is seeded per pixel, so renders are reproducible
+    assert!(camera.colour_for_pixel(&world, 5, 5).approx_eq(result));
+  }
+
+  #[test]
+  fn adaptive_sampling_refines_edges_only() {
+    let world = edge_world();
+    let camera =
+      Camera::new(11, 11, PI / 2.0).with_samples(4, Sampling::Adaptive { threshold: 0.1 });
+    let result = camera.colour_for_pixel(&world, 5, 5);
+    assert!(result.approx_eq(Colour::new(0.5, 0.5, 0.5)));
+    assert!(camera
+      .colour_for_pixel(&world, 1, 5)
+      .approx_eq(Colour::BLACK));
+    assert!(camera
+      .colour_for_pixel(&world, 9, 5)
+      .approx_eq(Colour::WHITE));
+  }
+
+  #[test]
+  fn adaptive_sampling_keeps_corners() {
+    // Only the top left corner of the pixel is white
+    #[derive(Debug)]
+    struct Corner(Vector);
+    impl Integrator for Corner {
+      fn colour_at(&self, _world: &World, ray: Ray, _rng: &mut Rng) -> Colour {
+        if ray.direction.approx_eq(self.0) {
+          Colour::WHITE
+        } else {
+          Colour::BLACK
+        }
+      }
+    }
+
+    let camera = Camera::new(1, 1, PI / 2.0);
+    let corner = camera.ray_for_subpixel(0, 0, 0.0, 0.0).direction;
+    let camera = camera
+      .with_samples(16, Sampling::Adaptive { threshold: 0.1 })
+      .with_integrator(Corner(corner));
+    let result = camera.colour_for_pixel(&World::new(), 0, 0);
+    assert!(result.approx_eq(Colour::WHITE * (1.0 / 20.0)));
+  }
+
+  #[test]
+  fn path_traced_render() {
+    let mut world = World::new();
+    world.background = Background::Solid(Colour::WHITE);
+    let mut sphere = Sphere::new();
+    sphere.transform = Transform::new(Matrix4x4::translation(0.0, 0.0, -5.0));
+    sphere.material.diffuse = 0.5;
+    world.add_object(Box::new(sphere));
+
+    let camera = Camera::new(11, 11, PI / 2.0)
+      .with_samples(4, Sampling::Jittered)
+      .with_integrator(PathTracer::default());
+    let result = camera.colour_for_pixel(&world, 5, 5);
+    assert!(result.approx_eq(Colour::new(0.5, 0.5, 0.5)));
+    let result = camera.colour_for_pixel(&world, 0, 0);
+    assert!(result.approx_eq(Colour::WHITE));
+  }
+
+  #[test]
+  fn render_delegates_to_integrator() {
+    #[derive(Debug)]
+    struct Direction;
+    impl Integrator for Direction {
+      fn colour_at(&self, _world: &World, ray: Ray, _rng: &mut Rng) -> Colour {
+        Colour::new(ray.direction.x, ray.direction.y, ray.direction.z)
+      }
+    }
+
+    let camera = Camera::new(11, 11, PI / 2.0).with_integrator(Direction);
+    let canvas = camera.render(&World::new());
+    let result = canvas.pixel_at(5, 5);
+    assert!(result.approx_eq(Colour::new(0.0, 0.0, -1.0)));
+  }
+
+  #[test]
+  fn render_img_applies_tone_mapping() {
+    let mut world = World::new();
+    world.background = Background::Solid(Colour::new(3.0, 1.0, 0.0));
+    let tone_mapping = ToneMapping::new(ToneMapOperator::Reinhard)
+      .with_exposure(-1.0)
+      .with_transfer(TransferFunction::Linear);
+
+    let camera = Camera::new(3, 3, PI / 2.0);
+    assert_eq!(
+      *camera.render_img(&world).get_pixel(1, 1),
+      Rgb([255, 255, 0])
+    );
+    let camera = camera.with_tone_mapping(tone_mapping);
+    assert_eq!(
+      *camera.render_img(&world).get_pixel(1, 1),
+      Rgb([153, 85, 0])
+    );
+  }
 }
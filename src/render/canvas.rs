@@ -1,7 +1,16 @@
 use crate::colour::Colour;
-use image::{ImageBuffer, Rgb, RgbImage};
-use std::fmt::Write as _;
+use crate::tone_map::ToneMapping;
+use image::codecs::hdr::HdrEncoder;
+use image::codecs::openexr::OpenExrEncoder;
+use image::error::ImageFormatHint;
+use image::{
+  ColorType, ImageBuffer, ImageEncoder, ImageError, ImageResult, Rgb, Rgb32FImage, RgbImage,
+};
+use std::fs::File;
+use std::io::{BufWriter, Seek, Write};
+use std::path::Path;
 
+#[derive(Debug, Clone)]
 pub struct Canvas {
   pub width: usize,
   pub height: usize,
@@ -34,59 +43,103 @@ impl Canvas {
     *self.canvas.get(y * self.width + x).unwrap()
   }
 
-  /// Returns the canvas formatted as a PPM image
+  /// Returns the canvas formatted as a PPM image, clamping colours to `[0, 1]` and encoding them
+  /// as sRGB
   pub fn to_ppm(&self) -> Result<String, std::fmt::Error> {
-    const MAX_COLOUR_VALUE: usize = 255;
-
-    let (width, height) = (self.width, self.height);
-    let mut string = String::new();
-    // Header
-    writeln!(string, "P3")?;
-    writeln!(string, "{width} {height}")?;
-    writeln!(string, "{MAX_COLOUR_VALUE}")?;
-    // Body
-    for y in 0..height {
-      let mut line_start = string.len();
-      for x in 0..width {
-        let colour = self.pixel_at(x, y);
-        for value in [colour.red, colour.green, colour.blue] {
-          // cast to the range 0-255
-          let value =
-            ((value.clamp(0.0, 1.0) * MAX_COLOUR_VALUE as f32).round() as usize).to_string();
-
-          if string.len() + value.len() - line_start >= 70 {
-            string.pop(); // remove the trailing space from the previous line
-            string.push('\n');
-            line_start = string.len();
-          }
-          write!(string, "{value} ").unwrap();
-        }
-      }
-      string.pop(); // remove the trailing space from the previous line
-      string.push('\n');
-    }
+    self.to_ppm_with(&ToneMapping::default())
+  }
 
-    Ok(string)
+  /// Returns the canvas formatted as a PPM image, tone mapping colours to `[0, 1]`
+  pub fn to_ppm_with(&self, tone_mapping: &ToneMapping) -> Result<String, std::fmt::Error> {
+    let mut bytes = Vec::new();
+    self
+      .write_ppm(&mut bytes, tone_mapping)
+      .map_err(|_| std::fmt::Error)?;
+    String::from_utf8(bytes).map_err(|_| std::fmt::Error)
   }
 
-  /// Returns the canvas as an ImageBuffer
+  /// Returns the canvas as an ImageBuffer, clamping colours to `[0, 1]` and encoding them as sRGB
   pub fn to_image(&self) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    self.to_image_with(&ToneMapping::default())
+  }
+
+  /// Returns the canvas as an ImageBuffer, tone mapping colours to `[0, 1]`
+  pub fn to_image_with(&self, tone_mapping: &ToneMapping) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
     let mut img: RgbImage = ImageBuffer::new(self.width as _, self.height as _);
 
     img
       .pixels_mut()
       .zip(&self.canvas)
       .for_each(|(pixel, colour)| {
-        *pixel = (*colour).into();
+        *pixel = tone_mapping.to_rgb8(*colour);
       });
 
     img
   }
+
+  /// Returns the canvas as an unclamped 32-bit float ImageBuffer
+  pub fn to_image_f32(&self) -> Rgb32FImage {
+    let mut img: Rgb32FImage = ImageBuffer::new(self.width as _, self.height as _);
+
+    img
+      .pixels_mut()
+      .zip(&self.canvas)
+      .for_each(|(pixel, colour)| {
+        *pixel = Rgb([colour.red, colour.green, colour.blue]);
+      });
+
+    img
+  }
+
+  /// Writes the canvas as a Radiance RGBE (`.hdr`) image. Negative values are written as zero.
+  pub fn write_hdr(&self, writer: impl Write) -> ImageResult<()> {
+    let pixels: Vec<Rgb<f32>> = self
+      .canvas
+      .iter()
+      .map(|colour| Rgb([colour.red, colour.green, colour.blue].map(|value| value.max(0.0))))
+      .collect();
+    HdrEncoder::new(writer).encode(&pixels, self.width, self.height)
+  }
+
+  /// Writes the canvas as a 32-bit float OpenEXR image
+  pub fn write_exr(&self, writer: impl Write + Seek) -> ImageResult<()> {
+    let img = self.to_image_f32();
+    let bytes: Vec<u8> = img
+      .as_raw()
+      .iter()
+      .flat_map(|value| value.to_ne_bytes())
+      .collect();
+    OpenExrEncoder::new(writer).write_image(
+      &bytes,
+      self.width as _,
+      self.height as _,
+      ColorType::Rgb32F,
+    )
+  }
+
+  /// Saves the canvas without clamping, as OpenEXR, Radiance HDR or PFM depending on the file
+  /// extension (`exr`, `hdr` or `pfm`)
+  pub fn save_hdr(&self, path: impl AsRef<Path>) -> ImageResult<()> {
+    let path = path.as_ref();
+    let extension = path
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+      Some("exr") => self.write_exr(BufWriter::new(File::create(path)?)),
+      Some("hdr") => self.write_hdr(BufWriter::new(File::create(path)?)),
+      Some("pfm") => Ok(self.write_pfm(BufWriter::new(File::create(path)?))?),
+      _ => Err(ImageError::Unsupported(
+        ImageFormatHint::PathExtension(path.into()).into(),
+      )),
+    }
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::tone_map::{ToneMapOperator, TransferFunction};
 
   #[test]
   fn new_canvas() {
@@ -127,7 +180,7 @@ mod tests {
     canvas.write_pixel(2, 1, (0.0, 0.5, 0.0).into());
     canvas.write_pixel(4, 2, (-0.5, 0.0, 1.0).into());
 
-    let ppm = canvas.to_ppm().unwrap();
+    let ppm = canvas.to_ppm_with(&ToneMapping::LEGACY).unwrap();
     let result = ppm.lines().skip(3).take(3).collect::<Vec<_>>();
     let expected = vec![
       "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0",
@@ -147,7 +200,7 @@ mod tests {
         canvas.write_pixel(x, y, colour);
       }
     }
-    let ppm = canvas.to_ppm().unwrap();
+    let ppm = canvas.to_ppm_with(&ToneMapping::LEGACY).unwrap();
     let result = ppm.lines().skip(3).take(4).collect::<Vec<_>>();
     let expected = vec![
       "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
@@ -159,6 +212,67 @@ mod tests {
     assert_eq!(result, expected);
   }
 
+  fn hdr_canvas() -> Canvas {
+    let mut canvas = Canvas::new(3, 2);
+    canvas.write_pixel(0, 0, (4.0, 0.5, 0.25).into());
+    canvas.write_pixel(2, 1, (0.0, 12.0, 1.0).into());
+    canvas
+  }
+
+  #[test]
+  fn to_image_with_tone_mapping() {
+    let mut canvas = Canvas::new(2, 1);
+    canvas.write_pixel(0, 0, (1.0, 3.0, 0.0).into());
+    canvas.write_pixel(1, 0, (0.5, 1.5, -1.0).into());
+
+    let img = canvas.to_image();
+    assert_eq!(*img.get_pixel(0, 0), Rgb([255, 255, 0]));
+    assert_eq!(*img.get_pixel(1, 0), Rgb([188, 255, 0]));
+
+    let tone_mapping =
+      ToneMapping::new(ToneMapOperator::Reinhard).with_transfer(TransferFunction::Linear);
+    let img = canvas.to_image_with(&tone_mapping);
+    assert_eq!(*img.get_pixel(0, 0), Rgb([128, 191, 0]));
+    assert_eq!(*img.get_pixel(1, 0), Rgb([85, 153, 0]));
+  }
+
+  #[test]
+  fn to_image_f32_is_unclamped() {
+    let img = hdr_canvas().to_image_f32();
+    assert_eq!(*img.get_pixel(0, 0), Rgb([4.0, 0.5, 0.25]));
+    assert_eq!(*img.get_pixel(2, 1), Rgb([0.0, 12.0, 1.0]));
+  }
+
+  #[test]
+  fn hdr_round_trip() {
+    let mut bytes = Vec::new();
+    hdr_canvas().write_hdr(&mut bytes).unwrap();
+
+    let decoder = image::codecs::hdr::HdrDecoder::new(bytes.as_slice()).unwrap();
+    let pixels = decoder.read_image_hdr().unwrap();
+    assert_eq!(pixels.len(), 6);
+    // RGBE shares one exponent between the channels, so small channels lose precision
+    assert_eq!(pixels[0], Rgb([4.0, 0.5, 0.25]));
+    assert_eq!(pixels[5][1], 12.0);
+  }
+
+  #[test]
+  fn exr_round_trip() {
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    hdr_canvas().write_exr(&mut bytes).unwrap();
+
+    let img = image::load_from_memory_with_format(bytes.get_ref(), image::ImageFormat::OpenExr)
+      .unwrap()
+      .to_rgb32f();
+    assert_eq!(img, hdr_canvas().to_image_f32());
+  }
+
+  #[test]
+  fn save_hdr_rejects_unknown_extension() {
+    let result = hdr_canvas().save_hdr("image.png");
+    assert!(matches!(result, Err(ImageError::Unsupported(_))));
+  }
+
   #[test]
   fn ppm_ends_with_newline() {
     let canvas = Canvas::new(5, 3);
@@ -1,4 +1,5 @@
 use crate::math::*;
+use crate::tone_map::ToneMapping;
 use image::Rgb;
 use std::ops::{Add, Mul, Sub};
 
@@ -9,14 +10,10 @@ pub struct Colour {
   pub blue: f32,
 }
 
+/// Clamps the colour and encodes it as sRGB, see [`ToneMapping::to_rgb8`]
 impl From<Colour> for Rgb<u8> {
   fn from(colour: Colour) -> Rgb<u8> {
-    [
-      (colour.red.clamp(0.0, 1.0) * 255.0).round() as u8,
-      (colour.green.clamp(0.0, 1.0) * 255.0).round() as u8,
-      (colour.blue.clamp(0.0, 1.0) * 255.0).round() as u8,
-    ]
-    .into()
+    ToneMapping::default().to_rgb8(colour)
   }
 }
 
@@ -123,6 +120,12 @@ impl Colour {
     Colour { red, green, blue }
   }
 
+  /// Linear colour from sRGB-encoded channels in `[0, 1]`, such as values picked from an image
+  /// editor (divide 8-bit values by 255)
+  pub fn from_srgb(red: f32, green: f32, blue: f32) -> Self {
+    Colour::new(red, green, blue).srgb_to_linear()
+  }
+
   pub fn hadamard_product(self, rhs: Colour) -> Colour {
     Colour {
       red: self.red * rhs.red,
@@ -138,6 +141,35 @@ impl Colour {
       blue: self.blue.clamp(min, max),
     }
   }
+
+  /// Applies the sRGB transfer function to every channel, encoding a linear colour for display
+  pub fn linear_to_srgb(self) -> Colour {
+    let encode = |value: f32| {
+      if value <= 0.0031308 {
+        value * 12.92
+      } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+      }
+    };
+    Colour::new(encode(self.red), encode(self.green), encode(self.blue))
+  }
+
+  /// Inverse of [`Colour::linear_to_srgb`], decoding an sRGB colour to linear
+  pub fn srgb_to_linear(self) -> Colour {
+    let decode = |value: f32| {
+      if value <= 0.04045 {
+        value / 12.92
+      } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+      }
+    };
+    Colour::new(decode(self.red), decode(self.green), decode(self.blue))
+  }
+
+  /// Relative luminance of the (linear) colour, using the Rec. 709 weights
+  pub fn luminance(self) -> f32 {
+    0.2126 * self.red + 0.7152 * self.green + 0.0722 * self.blue
+  }
 }
 
 #[cfg(test)]
@@ -215,4 +247,35 @@ mod tests {
     let result = colour_a * colour_b;
     assert!(result.approx_eq(expected));
   }
+
+  #[test]
+  fn luminance_of_colour() {
+    assert!(Colour::WHITE.luminance().approx_eq(1.0));
+    assert!(Colour::BLACK.luminance().approx_eq(0.0));
+    assert!(Colour::GREEN.luminance().approx_eq(0.7152));
+  }
+
+  #[test]
+  fn srgb_transfer() {
+    let linear = Colour::new(0.0, 0.5, 1.0);
+    let encoded = linear.linear_to_srgb();
+    assert!(encoded.approx_eq(Colour::new(0.0, 0.735357, 1.0)));
+    assert!(encoded.srgb_to_linear().approx_eq(linear));
+    // The linear segment near black
+    assert!(Colour::new(0.002, 0.0, 0.0)
+      .linear_to_srgb()
+      .approx_eq(Colour::new(0.02584, 0.0, 0.0)));
+  }
+
+  #[test]
+  fn colour_from_srgb() {
+    let colour = Colour::from_srgb(0.5, 1.0, 0.0);
+    assert!(colour.approx_eq(Colour::new(0.214041, 1.0, 0.0)));
+  }
+
+  #[test]
+  fn colour_to_rgb8_is_srgb_encoded() {
+    let rgb: Rgb<u8> = Colour::new(0.5, 1.5, -0.5).into();
+    assert_eq!(rgb, Rgb([188, 255, 0]));
+  }
 }
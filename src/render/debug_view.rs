@@ -0,0 +1,190 @@
+use crate::*;
+
+// Integrators that visualise the scene's geometry instead of shading it, to tell whether a broken
+// render is caused by geometry, transforms or materials.
+
+/// Surface normals of the first hit, mapped from `[-1, 1]` to RGB in `[0, 1]`. Misses are black.
+#[derive(Debug, Clone, Default)]
+pub struct NormalsView;
+
+impl Integrator for NormalsView {
+  fn colour_at(&self, world: &World, ray: Ray, _rng: &mut Rng) -> Colour {
+    let intersections = world.intersect(ray);
+    let Some(hit) = intersections.hit() else {
+      return Colour::BLACK;
+    };
+    let normal = hit.prepare_computations_with(ray, &intersections).normal;
+    Colour::new(
+      (normal.x + 1.0) / 2.0,
+      (normal.y + 1.0) / 2.0,
+      (normal.z + 1.0) / 2.0,
+    )
+  }
+}
+
+/// Distance to the first hit as a shade of grey, from black at the camera to white at `far`
+/// and beyond. Misses are white.
+#[derive(Debug, Clone)]
+pub struct DepthView {
+  pub far: f32,
+}
+
+impl DepthView {
+  pub fn new(far: f32) -> Self {
+    DepthView { far }
+  }
+}
+
+impl Integrator for DepthView {
+  fn colour_at(&self, world: &World, ray: Ray, _rng: &mut Rng) -> Colour {
+    let depth = match world.intersect(ray).hit() {
+      Some(hit) => (hit.t * ray.direction.magnitude() / self.far).clamp(0.0, 1.0),
+      None => 1.0,
+    };
+    Colour::new(depth, depth, depth)
+  }
+}
+
+/// A distinct colour for each of the world's top level objects, so groups and meshes are shown
+/// as a single object. Colours depend only on the object's index, so they are stable between
+/// renders of the same world. Misses are black.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectIdView;
+
+impl ObjectIdView {
+  pub fn colour_for(index: usize) -> Colour {
+    let mut rng = Rng::new(index as u64);
+    hue(rng.next_f32())
+  }
+}
+
+impl Integrator for ObjectIdView {
+  fn colour_at(&self, world: &World, ray: Ray, _rng: &mut Rng) -> Colour {
+    let intersections = world.intersect(ray);
+    let Some(hit) = intersections.hit() else {
+      return Colour::BLACK;
+    };
+    world
+      .objects()
+      .iter()
+      .position(|object| object.includes(hit.object))
+      .map_or(Colour::BLACK, ObjectIdView::colour_for)
+  }
+}
+
+/// Number of intersection tests needed to find the first hit (see [`intersection_tests`]), from
+/// blue for none to red for `max_tests` or more. Requires the `heatmap` feature.
+#[cfg(feature = "heatmap")]
+#[derive(Debug, Clone)]
+pub struct HeatmapView {
+  pub max_tests: usize,
+}
+
+#[cfg(feature = "heatmap")]
+impl HeatmapView {
+  pub fn new(max_tests: usize) -> Self {
+    HeatmapView {
+      max_tests: max_tests.max(1),
+    }
+  }
+}
+
+#[cfg(feature = "heatmap")]
+impl Integrator for HeatmapView {
+  fn colour_at(&self, world: &World, ray: Ray, _rng: &mut Rng) -> Colour {
+    let before = intersection_tests();
+    world.intersect(ray);
+    let tests = intersection_tests() - before;
+
+    let heat = (tests as f32 / self.max_tests as f32).min(1.0);
+    hue((1.0 - heat) * 2.0 / 3.0)
+  }
+}
+
+/// Fully saturated colour for a hue in `[0, 1)`, where 0 is red, 1/3 green and 2/3 blue
+fn hue(hue: f32) -> Colour {
+  let h = hue.rem_euclid(1.0) * 6.0;
+  let x = 1.0 - (h % 2.0 - 1.0).abs();
+  match h as usize {
+    0 => Colour::new(1.0, x, 0.0),
+    1 => Colour::new(x, 1.0, 0.0),
+    2 => Colour::new(0.0, 1.0, x),
+    3 => Colour::new(0.0, x, 1.0),
+    4 => Colour::new(x, 0.0, 1.0),
+    _ => Colour::new(1.0, 0.0, x),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn hue_primaries() {
+    assert!(hue(0.0).approx_eq(Colour::RED));
+    assert!(hue(1.0 / 3.0).approx_eq(Colour::GREEN));
+    assert!(hue(2.0 / 3.0).approx_eq(Colour::BLUE));
+  }
+
+  #[test]
+  fn normals_view() {
+    let world = World::default();
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let result = NormalsView.colour_at(&world, ray, &mut Rng::new(0));
+    assert!(result.approx_eq(Colour::new(0.5, 0.5, 0.0)));
+
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 1.0, 0.0));
+    let result = NormalsView.colour_at(&world, ray, &mut Rng::new(0));
+    assert!(result.approx_eq(Colour::BLACK));
+  }
+
+  #[test]
+  fn depth_view() {
+    let world = World::default();
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let result = DepthView::new(8.0).colour_at(&world, ray, &mut Rng::new(0));
+    assert!(result.approx_eq(Colour::new(0.5, 0.5, 0.5)));
+
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 1.0, 0.0));
+    let result = DepthView::new(8.0).colour_at(&world, ray, &mut Rng::new(0));
+    assert!(result.approx_eq(Colour::WHITE));
+  }
+
+  #[test]
+  fn object_id_view_is_stable_per_object() {
+    let mut world = World::new();
+    let mut left = Sphere::new();
+    left.transform = Transform::new(Matrix4x4::translation(-2.0, 0.0, 0.0));
+    let mut group = Group::new();
+    group.add_child(Box::new(Sphere::new()));
+    world.add_object(Box::new(left));
+    world.add_object(Box::new(group));
+
+    let left_ray = Ray::new((-2.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let centre_ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let left = ObjectIdView.colour_at(&world, left_ray, &mut Rng::new(0));
+    let centre = ObjectIdView.colour_at(&world, centre_ray, &mut Rng::new(0));
+    assert!(left.approx_eq(ObjectIdView::colour_for(0)));
+    assert!(centre.approx_eq(ObjectIdView::colour_for(1)));
+    assert!(!left.approx_eq(centre));
+  }
+
+  #[cfg(feature = "heatmap")]
+  #[test]
+  fn heatmap_counts_intersection_tests() {
+    let world = World::default();
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+
+    // Both spheres, plus the boxes of the bounding volume hierarchy around them
+    let before = intersection_tests();
+    world.intersect(ray);
+    let tests = intersection_tests() - before;
+    assert!(tests > 2);
+
+    let result = HeatmapView::new(tests * 2).colour_at(&world, ray, &mut Rng::new(0));
+    assert!(result.approx_eq(hue(1.0 / 3.0)));
+
+    let result = HeatmapView::new(tests).colour_at(&world, ray, &mut Rng::new(0));
+    assert!(result.approx_eq(Colour::RED));
+  }
+}
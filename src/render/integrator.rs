@@ -0,0 +1,62 @@
+use crate::*;
+use std::fmt::Debug;
+
+/// Computes the colour seen along camera rays; the camera delegates all shading to one of these.
+pub trait Integrator: Debug + Send + Sync {
+  /// Colour arriving along `ray`. Stochastic integrators draw their random numbers from `rng`,
+  /// which is seeded per pixel so that renders are reproducible.
+  fn colour_at(&self, world: &World, ray: Ray, rng: &mut Rng) -> Colour;
+}
+
+/// Whitted-style recursive ray tracing: direct lighting with hard or soft shadows, plus mirror
+/// reflection and refraction, as implemented by [`World::shade_hit`].
+#[derive(Debug, Clone)]
+pub struct Whitted {
+  /// Maximum number of reflected and refracted bounces
+  pub max_depth: usize,
+}
+
+impl Default for Whitted {
+  fn default() -> Self {
+    Whitted {
+      max_depth: MAX_RECURSION_DEPTH,
+    }
+  }
+}
+
+impl Integrator for Whitted {
+  fn colour_at(&self, world: &World, ray: Ray, _rng: &mut Rng) -> Colour {
+    world._colour_at(ray, self.max_depth)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn whitted_matches_world_colour_at() {
+    let world = World::default();
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let result = Whitted::default().colour_at(&world, ray, &mut Rng::new(0));
+    assert!(result.approx_eq(world.colour_at(ray)));
+  }
+
+  #[test]
+  fn whitted_respects_max_depth() {
+    let mut world = World::new();
+    let mut plane = Plane::new();
+    plane.material.reflective = 1.0;
+    plane.material.ambient = 0.0;
+    plane.material.diffuse = 0.0;
+    plane.material.specular = 0.0;
+    world.add_object(Box::new(plane));
+    world.background = Background::Solid(Colour::WHITE);
+
+    let ray = Ray::new((0.0, 1.0, -1.0), (0.0, -1.0, 1.0));
+    let reflected = Whitted::default().colour_at(&world, ray, &mut Rng::new(0));
+    assert!(reflected.approx_eq(Colour::WHITE));
+    let unreflected = Whitted { max_depth: 0 }.colour_at(&world, ray, &mut Rng::new(0));
+    assert!(unreflected.approx_eq(Colour::BLACK));
+  }
+}
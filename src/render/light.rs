@@ -1,8 +1,146 @@
 use crate::*;
+use std::f32::consts::PI;
+use std::fmt::Debug;
 
+/// The direction and distance from a position being shaded to a point on a light.
+#[derive(Debug, Copy, Clone)]
+pub struct LightSample {
+  /// Normalised vector from the shaded position towards the light
+  pub direction: Vector,
+  /// Distance to the light; occluders further away than this cast no shadow. Infinite for lights
+  /// without a position, such as [`DirectionalLight`].
+  pub distance: f32,
+  /// Intensity of the light arriving from this sample
+  pub intensity: Colour,
+}
+
+impl LightSample {
+  /// Sample of a light at `light_position` as seen from `position`
+  pub fn between(position: Point, light_position: Point, intensity: Colour) -> Self {
+    let point_to_light = light_position - position;
+    let distance = point_to_light.magnitude();
+    LightSample {
+      direction: point_to_light.normalise(),
+      distance,
+      intensity,
+    }
+  }
+}
+
+/// How a light's intensity falls off with distance, as `1 / (constant + linear * d + quadratic * d²)`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Attenuation {
+  constant: f32,
+  linear: f32,
+  quadratic: f32,
+}
+
+/// Smallest denominator used by [`Attenuation::factor`], which bounds the brightness right next
+/// to a light
+const MIN_ATTENUATION_DENOMINATOR: f32 = 1e-4;
+
+impl Attenuation {
+  /// Constant intensity at any distance
+  pub const NONE: Self = Attenuation {
+    constant: 1.0,
+    linear: 0.0,
+    quadratic: 0.0,
+  };
+
+  /// Physically based falloff with the square of the distance
+  pub const INVERSE_SQUARE: Self = Attenuation {
+    constant: 0.0,
+    linear: 0.0,
+    quadratic: 1.0,
+  };
+
+  /// Panics if the coefficients are invalid; use [`Attenuation::try_new`] for values that may be
+  /// invalid
+  pub fn new(constant: f32, linear: f32, quadratic: f32) -> Self {
+    Attenuation::try_new(constant, linear, quadratic)
+      .expect("attenuation coefficients must be non-negative and not all zero")
+  }
+
+  /// Returns `None` if any coefficient is negative or not finite, or if they are all zero
+  pub fn try_new(constant: f32, linear: f32, quadratic: f32) -> Option<Self> {
+    let coefficients = [constant, linear, quadratic];
+    let valid = coefficients
+      .iter()
+      .all(|coefficient| coefficient.is_finite() && *coefficient >= 0.0)
+      && coefficients.iter().any(|coefficient| *coefficient > 0.0);
+    valid.then_some(Attenuation {
+      constant,
+      linear,
+      quadratic,
+    })
+  }
+
+  /// Fraction of the intensity remaining at `distance` from the light
+  pub fn factor(&self, distance: f32) -> f32 {
+    if *self == Attenuation::NONE || distance.is_infinite() {
+      return 1.0;
+    }
+    let denominator = self.constant + self.linear * distance + self.quadratic * distance * distance;
+    1.0 / denominator.max(MIN_ATTENUATION_DENOMINATOR)
+  }
+}
+
+impl Default for Attenuation {
+  fn default() -> Self {
+    Attenuation::NONE
+  }
+}
+
+pub trait Light: Debug + Send + Sync {
+  fn intensity(&self) -> Colour;
+
+  /// Falloff applied to the light's samples by [`Material::lighting`]
+  fn attenuation(&self) -> Attenuation {
+    Attenuation::NONE
+  }
+
+  /// Whether the light's samples come from `background`, like an [`EnvironmentLight`] whose map is
+  /// also the background. Integrators that sample the light directly then leave the background
+  /// out of rays escaping after a diffuse bounce, to avoid counting it twice.
+  fn samples_background(&self, _background: &Background) -> bool {
+    false
+  }
+
+  /// The points on the light used to shade `position`, and to test it for shadows.
+  fn samples(&self, position: Point) -> Vec<LightSample>;
+
+  /// The light's samples for `position`, with a black intensity for those occluded by objects in
+  /// the world
+  fn visible_samples(&self, position: Point, world: &World) -> Vec<LightSample> {
+    let mut samples = self.samples(position);
+    for sample in &mut samples {
+      if world.is_shadowed(position, sample) {
+        sample.intensity = Colour::BLACK;
+      }
+    }
+    samples
+  }
+
+  /// Fraction of the light's samples that reach the position unoccluded, from 0.0 (fully in
+  /// shadow) to 1.0 (fully lit).
+  fn intensity_at(&self, position: Point, world: &World) -> f32 {
+    let samples = self.samples(position);
+    if samples.is_empty() {
+      return 0.0;
+    }
+    let lit = samples
+      .iter()
+      .filter(|sample| !world.is_shadowed(position, sample))
+      .count();
+    lit as f32 / samples.len() as f32
+  }
+}
+
+#[derive(Debug, Clone)]
 pub struct PointLight {
   pub position: Point,
   pub intensity: Colour,
+  pub attenuation: Attenuation,
 }
 
 impl PointLight {
@@ -10,8 +148,32 @@ impl PointLight {
     PointLight {
       position: position.into(),
       intensity: intensity.into(),
+      attenuation: Attenuation::NONE,
     }
   }
+
+  pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+    self.attenuation = attenuation;
+    self
+  }
+}
+
+impl Light for PointLight {
+  fn intensity(&self) -> Colour {
+    self.intensity
+  }
+
+  fn attenuation(&self) -> Attenuation {
+    self.attenuation
+  }
+
+  fn samples(&self, position: Point) -> Vec<LightSample> {
+    vec![LightSample::between(
+      position,
+      self.position,
+      self.intensity,
+    )]
+  }
 }
 
 impl ApproxEq for PointLight {
@@ -20,9 +182,328 @@ impl ApproxEq for PointLight {
   }
 }
 
+/// A rectangular light that casts soft shadows.
+///
+/// The rectangle spanned by `corner`, `corner + full_uvec` and `corner + full_vvec` is divided
+/// into `usteps` x `vsteps` cells, and each cell contributes one sample. With `jitter` enabled the
+/// sample is placed at a random position within its cell, which trades banding in the penumbra for
+/// noise.
+#[derive(Debug, Clone)]
+pub struct AreaLight {
+  pub corner: Point,
+  uvec: Vector,
+  usteps: usize,
+  vvec: Vector,
+  vsteps: usize,
+  pub intensity: Colour,
+  pub attenuation: Attenuation,
+  pub jitter: bool,
+}
+
+impl AreaLight {
+  pub fn new(
+    corner: impl Into<Point>,
+    full_uvec: impl Into<Vector>,
+    usteps: usize,
+    full_vvec: impl Into<Vector>,
+    vsteps: usize,
+    intensity: impl Into<Colour>,
+  ) -> Self {
+    let (usteps, vsteps) = (usteps.max(1), vsteps.max(1));
+    AreaLight {
+      corner: corner.into(),
+      uvec: full_uvec.into() / usteps as f32,
+      usteps,
+      vvec: full_vvec.into() / vsteps as f32,
+      vsteps,
+      intensity: intensity.into(),
+      attenuation: Attenuation::NONE,
+      jitter: true,
+    }
+  }
+
+  pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+    self.attenuation = attenuation;
+    self
+  }
+
+  pub fn with_jitter(mut self, jitter: bool) -> Self {
+    self.jitter = jitter;
+    self
+  }
+
+  pub fn cells(&self) -> usize {
+    self.usteps * self.vsteps
+  }
+
+  /// Centre of the light
+  pub fn position(&self) -> Point {
+    self.corner + self.uvec * (self.usteps as f32 / 2.0) + self.vvec * (self.vsteps as f32 / 2.0)
+  }
+
+  /// Point at offset `(du, dv)` within cell `(u, v)`, where `(0.5, 0.5)` is the cell centre
+  pub fn point_on_light(&self, u: usize, v: usize, du: f32, dv: f32) -> Point {
+    self.corner + self.uvec * (u as f32 + du) + self.vvec * (v as f32 + dv)
+  }
+}
+
+impl Light for AreaLight {
+  fn intensity(&self) -> Colour {
+    self.intensity
+  }
+
+  fn attenuation(&self) -> Attenuation {
+    self.attenuation
+  }
+
+  fn samples(&self, position: Point) -> Vec<LightSample> {
+    // Seed the jitter from the shaded position so that the same position always sees the same
+    // samples, both when testing for shadows and when shading.
+    let mut rng = self.jitter.then(|| Rng::for_point(position));
+
+    let mut samples = Vec::with_capacity(self.cells());
+    for v in 0..self.vsteps {
+      for u in 0..self.usteps {
+        let (du, dv) = match rng.as_mut() {
+          Some(rng) => (rng.next_f32(), rng.next_f32()),
+          None => (0.5, 0.5),
+        };
+        samples.push(LightSample::between(
+          position,
+          self.point_on_light(u, v, du, dv),
+          self.intensity,
+        ));
+      }
+    }
+    samples
+  }
+}
+
+/// A point light that only shines within a cone around `direction`.
+///
+/// Light is at full intensity within `inner_angle` of the axis and fades smoothly to nothing at
+/// `outer_angle`. Both angles are measured from the axis, in radians.
+#[derive(Debug, Clone)]
+pub struct SpotLight {
+  pub position: Point,
+  direction: Vector,
+  cos_inner: f32,
+  cos_outer: f32,
+  pub intensity: Colour,
+  pub attenuation: Attenuation,
+}
+
+impl SpotLight {
+  pub fn new(
+    position: impl Into<Point>,
+    direction: impl Into<Vector>,
+    inner_angle: f32,
+    outer_angle: f32,
+    intensity: impl Into<Colour>,
+  ) -> Self {
+    let outer_angle = outer_angle.max(inner_angle);
+    SpotLight {
+      position: position.into(),
+      direction: direction.into().normalise(),
+      cos_inner: inner_angle.cos(),
+      cos_outer: outer_angle.cos(),
+      intensity: intensity.into(),
+      attenuation: Attenuation::NONE,
+    }
+  }
+
+  pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+    self.attenuation = attenuation;
+    self
+  }
+
+  pub fn direction(&self) -> Vector {
+    self.direction
+  }
+
+  /// Fraction of the intensity emitted towards `position`, from 1.0 inside the inner cone to 0.0
+  /// outside the outer cone
+  pub fn falloff(&self, position: Point) -> f32 {
+    let cos_angle = (position - self.position).normalise().dot(self.direction);
+    if cos_angle >= self.cos_inner {
+      1.0
+    } else if cos_angle <= self.cos_outer {
+      0.0
+    } else {
+      let t = (cos_angle - self.cos_outer) / (self.cos_inner - self.cos_outer);
+      t * t * (3.0 - 2.0 * t)
+    }
+  }
+}
+
+impl Light for SpotLight {
+  fn intensity(&self) -> Colour {
+    self.intensity
+  }
+
+  fn attenuation(&self) -> Attenuation {
+    self.attenuation
+  }
+
+  fn samples(&self, position: Point) -> Vec<LightSample> {
+    vec![LightSample::between(
+      position,
+      self.position,
+      self.intensity * self.falloff(position),
+    )]
+  }
+}
+
+/// A light infinitely far away, such as the sun, whose rays all travel in `direction`.
+#[derive(Debug, Clone)]
+pub struct DirectionalLight {
+  direction: Vector,
+  pub intensity: Colour,
+}
+
+impl DirectionalLight {
+  pub fn new(direction: impl Into<Vector>, intensity: impl Into<Colour>) -> Self {
+    DirectionalLight {
+      direction: direction.into().normalise(),
+      intensity: intensity.into(),
+    }
+  }
+
+  pub fn direction(&self) -> Vector {
+    self.direction
+  }
+}
+
+impl Light for DirectionalLight {
+  fn intensity(&self) -> Colour {
+    self.intensity
+  }
+
+  fn samples(&self, _position: Point) -> Vec<LightSample> {
+    vec![LightSample {
+      direction: -self.direction,
+      distance: f32::INFINITY,
+      intensity: self.intensity,
+    }]
+  }
+}
+
+/// Image-based lighting from an equirectangular environment map surrounding the world.
+///
+/// Each shaded position draws `samples` directions towards the map, chosen in proportion to the
+/// luminance of its pixels so that bright regions such as the sun are rarely missed. Sample
+/// intensities are weighted by the inverse of their probability, so that averaging them in
+/// [`Material::lighting`] estimates the irradiance from the whole environment.
+#[derive(Debug, Clone)]
+pub struct EnvironmentLight {
+  map: EnvironmentMap,
+  /// Cumulative sampling weight of the pixels, in row-major order
+  cdf: Vec<f32>,
+  average: Colour,
+  pub samples: usize,
+  /// Multiplier for the radiance of the map
+  pub strength: f32,
+}
+
+impl EnvironmentLight {
+  pub fn new(map: EnvironmentMap, samples: usize) -> Self {
+    let (width, height) = (map.width(), map.height());
+    let mut cdf = Vec::with_capacity(width * height);
+    let mut total = 0.0;
+    let mut average = Colour::BLACK;
+    for y in 0..height {
+      // Rows near the poles cover less solid angle than those at the horizon
+      let sin_theta = ((y as f32 + 0.5) / height as f32 * PI).sin();
+      for x in 0..width {
+        let pixel = map.pixel(x, y);
+        total += pixel.luminance().max(0.0) * sin_theta;
+        cdf.push(total);
+        average = average + pixel * sin_theta;
+      }
+    }
+    let solid_angle = 2.0 * PI * PI / (width * height) as f32;
+
+    EnvironmentLight {
+      map,
+      cdf,
+      average: average * (solid_angle / (4.0 * PI)),
+      samples: samples.max(1),
+      strength: 1.0,
+    }
+  }
+
+  /// Loads an environment map, such as a Radiance `.hdr` file
+  pub fn open(path: impl AsRef<std::path::Path>, samples: usize) -> image::ImageResult<Self> {
+    Ok(EnvironmentLight::new(EnvironmentMap::open(path)?, samples))
+  }
+
+  pub fn with_strength(mut self, strength: f32) -> Self {
+    self.strength = strength;
+    self
+  }
+
+  pub fn map(&self) -> &EnvironmentMap {
+    &self.map
+  }
+
+  /// Probability density, per unit solid angle, of sampling pixel `index`
+  fn pdf(&self, index: usize) -> f32 {
+    let (width, height) = (self.map.width(), self.map.height());
+    let total = self.cdf[self.cdf.len() - 1];
+    let previous = if index == 0 { 0.0 } else { self.cdf[index - 1] };
+    let sin_theta = (((index / width) as f32 + 0.5) / height as f32 * PI).sin();
+    (self.cdf[index] - previous) / total * (width * height) as f32 / (2.0 * PI * PI * sin_theta)
+  }
+}
+
+impl Light for EnvironmentLight {
+  fn intensity(&self) -> Colour {
+    self.average * self.strength
+  }
+
+  /// True only for a background sharing the light's map (see [`EnvironmentMap::same_image`]) at
+  /// the same strength
+  fn samples_background(&self, background: &Background) -> bool {
+    self.strength == 1.0
+      && matches!(background, Background::Environment(map) if map.same_image(&self.map))
+  }
+
+  fn samples(&self, position: Point) -> Vec<LightSample> {
+    let total = self.cdf.last().copied().unwrap_or(0.0);
+    if total <= 0.0 {
+      return vec![];
+    }
+
+    let (width, height) = (self.map.width(), self.map.height());
+    let mut rng = Rng::for_point(position);
+    (0..self.samples)
+      .map(|_| {
+        let target = rng.next_f32() * total;
+        let index = self
+          .cdf
+          .partition_point(|&weight| weight <= target)
+          .min(self.cdf.len() - 1);
+        let (x, y) = (index % width, index / width);
+        let u = (x as f32 + rng.next_f32()) / width as f32;
+        let v = (y as f32 + rng.next_f32()) / height as f32;
+
+        // Dividing by pi makes a uniform environment of radiance L light a diffuse surface like a
+        // point light of intensity L shining straight at it.
+        let weight = self.strength / (PI * self.pdf(index));
+        LightSample {
+          direction: EnvironmentMap::direction(u, v),
+          distance: f32::INFINITY,
+          intensity: self.map.pixel(x, y) * weight,
+        }
+      })
+      .collect()
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
+  use image::{DynamicImage, ImageBuffer, Rgb};
 
   #[test]
   fn construct_point_light() {
@@ -33,4 +514,340 @@ mod tests {
     assert!(light.position.approx_eq(position));
     assert_eq!(light.intensity, intensity);
   }
+
+  #[test]
+  fn point_light_intensity_at() {
+    let world = World::default();
+    let light = &world.lights[0];
+    let cases = [
+      ((0.0, 1.0001, 0.0), 1.0),
+      ((-1.0001, 0.0, 0.0), 1.0),
+      ((0.0, 0.0, -1.0001), 1.0),
+      ((0.0, 0.0, 1.0001), 0.0),
+      ((1.0001, 0.0, 0.0), 0.0),
+      ((0.0, -1.0001, 0.0), 0.0),
+      ((0.0, 0.0, 0.0), 0.0),
+    ];
+    for (position, expected) in cases {
+      let result = light.intensity_at(position.into(), &world);
+      assert!(result.approx_eq(expected));
+    }
+  }
+
+  #[test]
+  fn construct_area_light() {
+    let light = AreaLight::new(
+      (0.0, 0.0, 0.0),
+      (2.0, 0.0, 0.0),
+      4,
+      (0.0, 0.0, 1.0),
+      2,
+      Colour::WHITE,
+    );
+
+    assert!(light.corner.approx_eq(Point::ORIGIN));
+    assert!(light.uvec.approx_eq(Vector::new(0.5, 0.0, 0.0)));
+    assert!(light.vvec.approx_eq(Vector::new(0.0, 0.0, 0.5)));
+    assert_eq!(light.cells(), 8);
+    assert!(light.position().approx_eq(Point::new(1.0, 0.0, 0.5)));
+  }
+
+  #[test]
+  fn area_light_point_on_light() {
+    let light = AreaLight::new(
+      (0.0, 0.0, 0.0),
+      (2.0, 0.0, 0.0),
+      4,
+      (0.0, 0.0, 1.0),
+      2,
+      Colour::WHITE,
+    );
+    let cases = [
+      ((0, 0), (0.25, 0.0, 0.25)),
+      ((1, 0), (0.75, 0.0, 0.25)),
+      ((0, 1), (0.25, 0.0, 0.75)),
+      ((2, 0), (1.25, 0.0, 0.25)),
+      ((3, 1), (1.75, 0.0, 0.75)),
+    ];
+    for ((u, v), expected) in cases {
+      let result = light.point_on_light(u, v, 0.5, 0.5);
+      assert!(result.approx_eq(expected.into()));
+    }
+  }
+
+  #[test]
+  fn area_light_intensity_at() {
+    let world = World::default();
+    let light = AreaLight::new(
+      (-0.5, -0.5, -5.0),
+      (1.0, 0.0, 0.0),
+      2,
+      (0.0, 1.0, 0.0),
+      2,
+      Colour::WHITE,
+    )
+    .with_jitter(false);
+    let cases = [
+      ((0.0, 0.0, 2.0), 0.0),
+      ((1.0, -1.0, 2.0), 0.25),
+      ((1.5, 0.0, 2.0), 0.5),
+      ((1.25, 1.25, 3.0), 0.75),
+      ((0.0, 0.0, -2.0), 1.0),
+    ];
+    for (position, expected) in cases {
+      let result = light.intensity_at(position.into(), &world);
+      assert!(result.approx_eq(expected));
+    }
+  }
+
+  #[test]
+  fn jittered_samples_stay_in_cells() {
+    let light = AreaLight::new(
+      (0.0, 0.0, 0.0),
+      (2.0, 0.0, 0.0),
+      4,
+      (0.0, 0.0, 1.0),
+      2,
+      Colour::WHITE,
+    );
+    let position = Point::new(0.0, 5.0, 0.0);
+    let samples = light.samples(position);
+    assert_eq!(samples.len(), 8);
+    for (index, sample) in samples.iter().enumerate() {
+      let (u, v) = (index % 4, index / 4);
+      let point = position + sample.direction * sample.distance;
+      assert!((u as f32 * 0.5..=(u + 1) as f32 * 0.5).contains(&point.x));
+      assert!((v as f32 * 0.5..=(v + 1) as f32 * 0.5).contains(&point.z));
+    }
+    // The same position always sees the same samples
+    let again = light.samples(position);
+    assert!(samples
+      .iter()
+      .zip(again.iter())
+      .all(|(a, b)| a.direction.approx_eq(b.direction)));
+  }
+
+  #[test]
+  fn spot_light_falloff() {
+    let light = SpotLight::new(
+      (0.0, 10.0, 0.0),
+      (0.0, -1.0, 0.0),
+      PI / 8.0,
+      PI / 4.0,
+      Colour::WHITE,
+    );
+    let cases = [
+      ((0.0, 0.0, 0.0), 1.0),
+      ((1.0, 0.0, 0.0), 1.0),
+      ((20.0, 0.0, 0.0), 0.0),
+      ((0.0, 20.0, 0.0), 0.0),
+    ];
+    for (position, expected) in cases {
+      let result = light.falloff(position.into());
+      assert!(result.approx_eq(expected));
+    }
+
+    // Halfway between the cosines of the cone angles the smoothstep gives half intensity
+    let cos_angle = ((PI / 8.0).cos() + (PI / 4.0).cos()) / 2.0;
+    let x = 10.0 * (1.0 - cos_angle * cos_angle).sqrt() / cos_angle;
+    let sample = light.samples(Point::new(x, 0.0, 0.0))[0];
+    assert!(sample.intensity.approx_eq(Colour::new(0.5, 0.5, 0.5)));
+  }
+
+  #[test]
+  fn spot_light_lights_only_within_cone() {
+    let light = SpotLight::new(
+      (0.0, 10.0, 0.0),
+      (0.0, -1.0, 0.0),
+      PI / 8.0,
+      PI / 4.0,
+      Colour::WHITE,
+    );
+    let material = Material::default();
+    let eye_vector = Vector::new(0.0, 1.0, 0.0);
+    let normal_vector = Vector::new(0.0, 1.0, 0.0);
+
+    let position = Point::new(0.0, 0.0, 0.0);
+    let lit = material.lighting(&light, position, eye_vector, normal_vector, 1.0, position);
+    assert!(lit.approx_eq(Colour::new(1.9, 1.9, 1.9)));
+
+    let position = Point::new(20.0, 0.0, 0.0);
+    let unlit = material.lighting(&light, position, eye_vector, normal_vector, 1.0, position);
+    assert!(unlit.approx_eq(Colour::new(0.1, 0.1, 0.1)));
+  }
+
+  #[test]
+  fn directional_light_sample() {
+    let light = DirectionalLight::new((0.0, -2.0, 0.0), Colour::WHITE);
+    for position in [(0.0, 0.0, 0.0), (100.0, -5.0, 3.0)] {
+      let sample = light.samples(position.into())[0];
+      assert!(sample.direction.approx_eq(Vector::new(0.0, 1.0, 0.0)));
+      assert!(sample.distance.is_infinite());
+    }
+  }
+
+  #[test]
+  fn directional_light_shadows_are_parallel() {
+    let world = World::default();
+    let light = DirectionalLight::new((0.0, -1.0, 0.0), Colour::WHITE);
+    let cases = [
+      ((0.0, -1.0001, 0.0), 0.0),
+      ((0.9, -5.0, 0.0), 0.0),
+      ((0.0, -100.0, 0.9), 0.0),
+      ((1.1, -100.0, 0.0), 1.0),
+      ((0.0, 1.0001, 0.0), 1.0),
+    ];
+    for (position, expected) in cases {
+      let result = light.intensity_at(position.into(), &world);
+      assert!(result.approx_eq(expected));
+    }
+  }
+
+  #[test]
+  fn attenuation_factor() {
+    assert!(Attenuation::NONE.factor(10.0).approx_eq(1.0));
+    assert!(Attenuation::INVERSE_SQUARE.factor(2.0).approx_eq(0.25));
+    assert!(Attenuation::INVERSE_SQUARE
+      .factor(f32::INFINITY)
+      .approx_eq(1.0));
+    let attenuation = Attenuation::new(1.0, 0.5, 0.25);
+    assert!(attenuation.factor(2.0).approx_eq(1.0 / 3.0));
+  }
+
+  #[test]
+  fn inverse_square_attenuation_at_light() {
+    let touching = Attenuation::INVERSE_SQUARE.factor(0.0);
+    assert!(touching.is_finite());
+    assert!(touching >= Attenuation::INVERSE_SQUARE.factor(0.001));
+    assert!(touching > Attenuation::INVERSE_SQUARE.factor(0.1));
+  }
+
+  #[test]
+  fn invalid_attenuation_is_rejected() {
+    assert!(Attenuation::try_new(1.0, -0.5, 0.0).is_none());
+    assert!(Attenuation::try_new(0.0, 0.0, 0.0).is_none());
+    assert!(Attenuation::try_new(f32::NAN, 1.0, 0.0).is_none());
+    assert_eq!(
+      Attenuation::try_new(0.0, 0.0, 1.0),
+      Some(Attenuation::INVERSE_SQUARE)
+    );
+  }
+
+  #[test]
+  fn closer_attenuated_light_is_brighter() {
+    let material = Material {
+      ambient: 0.0,
+      specular: 0.0,
+      ..Default::default()
+    };
+    let position = Point::ORIGIN;
+    let normal_vector = Vector::new(0.0, 1.0, 0.0);
+    let shade = |height: f32| {
+      let light = PointLight::new((0.0, height, 0.0), Colour::WHITE)
+        .with_attenuation(Attenuation::INVERSE_SQUARE);
+      material.lighting(
+        &light,
+        position,
+        normal_vector,
+        normal_vector,
+        1.0,
+        position,
+      )
+    };
+
+    assert!(shade(1.0).approx_eq(Colour::new(0.9, 0.9, 0.9)));
+    assert!(shade(3.0).approx_eq(Colour::new(0.1, 0.1, 0.1)));
+  }
+
+  fn environment(width: u32, height: u32, colour: impl Fn(u32, u32) -> [f32; 3]) -> EnvironmentMap {
+    let image = ImageBuffer::from_fn(width, height, |x, y| Rgb(colour(x, y)));
+    EnvironmentMap::from_image(&DynamicImage::ImageRgb32F(image)).unwrap()
+  }
+
+  #[test]
+  fn uniform_environment_lights_like_white_furnace() {
+    let light = EnvironmentLight::new(environment(32, 16, |_, _| [1.0, 1.0, 1.0]), 4096);
+    assert!((light.intensity().red - 1.0).abs() < 0.01);
+
+    let material = Material {
+      ambient: 0.0,
+      specular: 0.0,
+      ..Default::default()
+    };
+    let position = Point::ORIGIN;
+    let normal_vector = Vector::new(0.0, 1.0, 0.0);
+    let result = material.lighting(
+      &light,
+      position,
+      normal_vector,
+      normal_vector,
+      1.0,
+      position,
+    );
+    assert!((result.red - 0.9).abs() < 0.05);
+  }
+
+  #[test]
+  fn environment_light_samples_bright_pixels() {
+    // Black everywhere except one bright pixel straight ahead of -z, on the horizon
+    let light = EnvironmentLight::new(
+      environment(8, 4, |x, y| {
+        if (x, y) == (4, 1) {
+          [10.0, 10.0, 10.0]
+        } else {
+          [0.0, 0.0, 0.0]
+        }
+      }),
+      16,
+    );
+    let samples = light.samples(Point::ORIGIN);
+    assert_eq!(samples.len(), 16);
+    for sample in samples {
+      let (u, v) = EnvironmentMap::uv(sample.direction);
+      assert!((0.5..=0.625).contains(&u) && (0.25..=0.5).contains(&v));
+      assert!(sample.distance.is_infinite());
+      assert!(sample.intensity.red > 0.0);
+    }
+  }
+
+  #[test]
+  fn environment_light_samples_only_its_own_background() {
+    let map = environment(4, 2, |_, _| [1.0, 1.0, 1.0]);
+    let light = EnvironmentLight::new(map.clone(), 4);
+    assert!(light.samples_background(&Background::Environment(map.clone())));
+    assert!(!light.samples_background(&Background::Solid(Colour::WHITE)));
+
+    // Equal pixels, but loaded separately
+    let other = environment(4, 2, |_, _| [1.0, 1.0, 1.0]);
+    assert!(!light.samples_background(&Background::Environment(other)));
+
+    let light = light.with_strength(2.0);
+    assert!(!light.samples_background(&Background::Environment(map)));
+  }
+
+  #[test]
+  fn black_environment_gives_no_light() {
+    let light = EnvironmentLight::new(environment(4, 2, |_, _| [0.0, 0.0, 0.0]), 8);
+    assert!(light.samples(Point::ORIGIN).is_empty());
+    let world = World::new();
+    assert!(light.intensity_at(Point::ORIGIN, &world).approx_eq(0.0));
+  }
+
+  #[test]
+  fn load_hdr_environment() {
+    let path = std::env::temp_dir().join("raytracer_challenge_environment_light.hdr");
+    let pixels = vec![Rgb([2.0f32, 4.0, 8.0]); 8 * 4];
+    let file = std::fs::File::create(&path).unwrap();
+    image::codecs::hdr::HdrEncoder::new(file)
+      .encode(&pixels, 8, 4)
+      .unwrap();
+
+    let light = EnvironmentLight::open(&path, 4).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!((light.map().width(), light.map().height()), (8, 4));
+    assert!(light
+      .map()
+      .pixel(3, 2)
+      .approx_eq(Colour::new(2.0, 4.0, 8.0)));
+  }
 }
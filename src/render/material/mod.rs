@@ -11,6 +11,8 @@ pub struct Material {
   pub specular: f32,
   pub shininess: f32,
   pub reflective: f32,
+  pub transparency: f32,
+  pub refractive_index: f32,
 }
 
 impl Default for Material {
@@ -22,49 +24,105 @@ impl Default for Material {
       specular: 0.9,
       shininess: 200.0,
       reflective: 0.0,
+      transparency: 0.0,
+      refractive_index: 1.0,
     }
   }
 }
 
 impl Material {
+  /// Colour of the surface lit by `light`, where `light_intensity` is the fraction of the light
+  /// that reaches the position (see [`Light::intensity_at`]). Diffuse and specular contributions
+  /// are averaged over the light's samples.
   pub fn lighting(
     &self,
-    light: &PointLight,
+    light: &dyn Light,
     position: Point,
     eye_vector: Vector,
     normal_vector: Vector,
-    in_shadow: bool,
+    light_intensity: f32,
     local_position: Point,
   ) -> Colour {
-    let effective_colour = self.pattern.colour_at(local_position) * light.intensity;
-    let light_vector = (light.position - position).normalise();
-    let ambient = effective_colour * self.ambient;
+    self.ambient_lighting(light, local_position)
+      + self.direct_lighting(
+        light,
+        position,
+        eye_vector,
+        normal_vector,
+        light_intensity,
+        local_position,
+      )
+  }
 
-    let light_dot_normal = light_vector.dot(normal_vector);
+  /// [`Material::lighting`] without the ambient term, for integrators that account for indirect
+  /// light themselves
+  pub fn direct_lighting(
+    &self,
+    light: &dyn Light,
+    position: Point,
+    eye_vector: Vector,
+    normal_vector: Vector,
+    light_intensity: f32,
+    local_position: Point,
+  ) -> Colour {
+    if light_intensity <= 0.0 {
+      return Colour::BLACK;
+    }
+    let samples = light.samples(position);
+    self.sample_lighting(light, &samples, eye_vector, normal_vector, local_position)
+      * light_intensity
+  }
 
-    let (diffuse, specular);
-    if light_dot_normal < 0.0 || in_shadow {
-      diffuse = Colour::BLACK;
-      specular = Colour::BLACK;
-    } else {
-      diffuse = effective_colour * self.diffuse * light_dot_normal;
+  /// Ambient light reflected by the surface, which doesn't depend on shadows
+  pub fn ambient_lighting(&self, light: &dyn Light, local_position: Point) -> Colour {
+    self.pattern.colour_at(local_position) * light.intensity() * self.ambient
+  }
+
+  /// Diffuse and specular light from `samples` of `light`, averaged over the samples. Occluded
+  /// samples should have a black intensity (see [`Light::visible_samples`]), so that every sample
+  /// is shadowed on its own.
+  pub fn sample_lighting(
+    &self,
+    light: &dyn Light,
+    samples: &[LightSample],
+    eye_vector: Vector,
+    normal_vector: Vector,
+    local_position: Point,
+  ) -> Colour {
+    if samples.is_empty() {
+      return Colour::BLACK;
+    }
+    let surface_colour = self.pattern.colour_at(local_position);
+    let attenuation = light.attenuation();
+    let sum = samples.iter().fold(Colour::BLACK, |sum, sample| {
+      let light_vector = sample.direction;
+      let light_dot_normal = light_vector.dot(normal_vector);
+      if light_dot_normal < 0.0 {
+        return sum;
+      }
+      let intensity = sample.intensity * attenuation.factor(sample.distance);
+      let effective_colour = surface_colour * intensity;
+      let diffuse = effective_colour * self.diffuse * light_dot_normal;
 
       let reflect_vector = (-light_vector).reflect(normal_vector);
       let reflect_dot_eye = reflect_vector.dot(eye_vector);
-      if reflect_dot_eye <= 0.0 {
-        specular = Colour::BLACK;
+      let specular = if reflect_dot_eye <= 0.0 {
+        Colour::BLACK
       } else {
-        specular = light.intensity * self.specular * reflect_dot_eye.powf(self.shininess)
-      }
-    }
+        intensity * self.specular * reflect_dot_eye.powf(self.shininess)
+      };
+
+      sum + diffuse + specular
+    });
 
-    ambient + diffuse + specular
+    sum * (1.0 / samples.len() as f32)
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use std::f32::consts::FRAC_1_SQRT_2;
 
   #[test]
   fn construct_default_material() {
@@ -76,6 +134,8 @@ mod tests {
       specular: 0.9,
       shininess: 200.0,
       reflective: 0.0,
+      transparency: 0.0,
+      refractive_index: 1.0,
     };
     assert!(matches!(_default_material, _expected))
   }
@@ -88,7 +148,7 @@ mod tests {
     let material = Material::default();
     let position: Point = (0.0, 0.0, 0.0).into();
 
-    let result = material.lighting(&light, position, eye_vector, normal_vector, false, position);
+    let result = material.lighting(&light, position, eye_vector, normal_vector, 1.0, position);
     let expected = Colour::from((1.9, 1.9, 1.9));
 
     assert!(result.approx_eq(expected));
@@ -102,7 +162,7 @@ mod tests {
     let material = Material::default();
     let position: Point = (0.0, 0.0, 0.0).into();
 
-    let result = material.lighting(&light, position, eye_vector, normal_vector, false, position);
+    let result = material.lighting(&light, position, eye_vector, normal_vector, 1.0, position);
     let expected = Colour::from((1.0, 1.0, 1.0));
 
     assert!(result.approx_eq(expected));
@@ -116,7 +176,7 @@ mod tests {
     let material = Material::default();
     let position: Point = (0.0, 0.0, 0.0).into();
 
-    let result = material.lighting(&light, position, eye_vector, normal_vector, false, position);
+    let result = material.lighting(&light, position, eye_vector, normal_vector, 1.0, position);
     let expected = Colour::from((0.7364, 0.7364, 0.7364));
 
     assert!(result.approx_eq(expected));
@@ -130,7 +190,7 @@ mod tests {
     let material = Material::default();
     let position: Point = (0.0, 0.0, 0.0).into();
 
-    let result = material.lighting(&light, position, eye_vector, normal_vector, false, position);
+    let result = material.lighting(&light, position, eye_vector, normal_vector, 1.0, position);
     let expected = Colour::from((1.6364, 1.6364, 1.6364));
 
     assert!(result.approx_eq(expected));
@@ -144,7 +204,7 @@ mod tests {
     let material = Material::default();
     let position: Point = (0.0, 0.0, 0.0).into();
 
-    let result = material.lighting(&light, position, eye_vector, normal_vector, false, position);
+    let result = material.lighting(&light, position, eye_vector, normal_vector, 1.0, position);
     let expected = Colour::from((0.1, 0.1, 0.1));
 
     assert!(result.approx_eq(expected));
@@ -159,9 +219,70 @@ mod tests {
     let normal_vector = Vector::new(0.0, 0.0, -1.0);
     let light = PointLight::new(Point::new(0.0, 0.00, -10.0), Colour::new(1.0, 1.0, 1.0));
 
-    let result = material.lighting(&light, position, eye_vector, normal_vector, true, position);
+    let result = material.lighting(&light, position, eye_vector, normal_vector, 0.0, position);
     let expected = Colour::new(0.1, 0.1, 0.1);
 
     assert!(result.approx_eq(expected));
   }
+
+  #[test]
+  fn lighting_uses_light_intensity() {
+    let light = PointLight::new((0.0, 0.0, -10.0), (1.0, 1.0, 1.0));
+    let material = Material {
+      ambient: 0.1,
+      diffuse: 0.9,
+      specular: 0.0,
+      pattern: Pattern::solid(Colour::WHITE),
+      ..Default::default()
+    };
+    let position = Point::new(0.0, 0.0, -1.0);
+    let eye_vector = Vector::new(0.0, 0.0, -1.0);
+    let normal_vector = Vector::new(0.0, 0.0, -1.0);
+
+    let cases = [(1.0, 1.0), (0.5, 0.55), (0.0, 0.1)];
+    for (intensity, expected) in cases {
+      let result = material.lighting(
+        &light,
+        position,
+        eye_vector,
+        normal_vector,
+        intensity,
+        position,
+      );
+      assert!(result.approx_eq(Colour::new(expected, expected, expected)));
+    }
+  }
+
+  #[test]
+  fn lighting_samples_area_light() {
+    let light = AreaLight::new(
+      (-0.5, -0.5, -5.0),
+      (1.0, 0.0, 0.0),
+      2,
+      (0.0, 1.0, 0.0),
+      2,
+      Colour::WHITE,
+    )
+    .with_jitter(false);
+    let material = Material {
+      ambient: 0.1,
+      diffuse: 0.9,
+      specular: 0.0,
+      pattern: Pattern::solid(Colour::WHITE),
+      ..Default::default()
+    };
+    let eye = Point::new(0.0, 0.0, -5.0);
+
+    let cases = [
+      ((0.0, 0.0, -1.0), 0.996505),
+      ((0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2), 0.623187),
+    ];
+    for (position, expected) in cases {
+      let position = Point::from(position);
+      let eye_vector = (eye - position).normalise();
+      let normal_vector = position - Point::ORIGIN;
+      let result = material.lighting(&light, position, eye_vector, normal_vector, 1.0, position);
+      assert!(result.approx_eq(Colour::new(expected, expected, expected)));
+    }
+  }
 }
@@ -3,14 +3,14 @@ use perlin2d::PerlinNoise2D;
 
 #[derive(Debug, Clone)]
 pub struct Pattern {
-  transform: Matrix4x4,
+  transform: Transform,
   pattern: PatternType,
 }
 
 impl Default for Pattern {
   fn default() -> Self {
     Pattern {
-      transform: Matrix4x4::IDENTITY,
+      transform: Transform::IDENTITY,
       pattern: Solid(Colour::WHITE),
     }
   }
@@ -21,60 +21,61 @@ impl Pattern {
   pub fn colour_at(&self, position: Point) -> Colour {
     self
       .pattern
-      .local_colour_at(self.transform.inverse().unwrap() * position)
+      .local_colour_at(self.transform.inverse() * position)
   }
 
   pub const fn solid(colour: Colour) -> Self {
     Pattern {
-      transform: Matrix4x4::IDENTITY,
+      transform: Transform::IDENTITY,
       pattern: Solid(colour),
     }
   }
 
   pub const fn test_pattern() -> Self {
     Pattern {
-      transform: Matrix4x4::IDENTITY,
+      transform: Transform::IDENTITY,
       pattern: Test,
     }
   }
 
   pub const fn stripe(colour_a: Colour, colour_b: Colour) -> Self {
     Pattern {
-      transform: Matrix4x4::IDENTITY,
+      transform: Transform::IDENTITY,
       pattern: Stripe(colour_a, colour_b),
     }
   }
 
   pub const fn gradient(colour_a: Colour, colour_b: Colour) -> Self {
     Pattern {
-      transform: Matrix4x4::IDENTITY,
+      transform: Transform::IDENTITY,
       pattern: Gradient(colour_a, colour_b),
     }
   }
 
   pub const fn ring(colour_a: Colour, colour_b: Colour) -> Self {
     Pattern {
-      transform: Matrix4x4::IDENTITY,
+      transform: Transform::IDENTITY,
       pattern: Ring(colour_a, colour_b),
     }
   }
 
   pub const fn checkers(colour_a: Colour, colour_b: Colour) -> Self {
     Pattern {
-      transform: Matrix4x4::IDENTITY,
+      transform: Transform::IDENTITY,
       pattern: Checkers(colour_a, colour_b),
     }
   }
 
   pub fn perlin(perlin_obj: PerlinNoise2D, factor: f32, pattern: PatternType) -> Self {
     Pattern {
-      transform: Matrix4x4::IDENTITY,
+      transform: Transform::IDENTITY,
       pattern: Perlin(perlin_obj, factor, Box::new(pattern)),
     }
   }
 
+  /// Panics if the matrix is not invertible
   pub fn with_transform(mut self, transform: Matrix4x4) -> Self {
-    self.transform = transform;
+    self.transform = Transform::new(transform);
     self
   }
 }
@@ -183,7 +184,7 @@ mod tests {
   #[test]
   fn default_pattern() {
     let pattern = Pattern::default();
-    assert!(pattern.transform.approx_eq(Matrix4x4::IDENTITY));
+    assert!(pattern.transform.matrix().approx_eq(Matrix4x4::IDENTITY));
     let _expected = PatternType::Solid(Colour::WHITE);
     assert!(matches!(pattern.pattern, _expected));
   }
@@ -193,6 +194,7 @@ mod tests {
     let pattern = Pattern::default().with_transform(Matrix4x4::translation(1.0, 2.0, 3.0));
     assert!(pattern
       .transform
+      .matrix()
       .approx_eq(Matrix4x4::translation(1.0, 2.0, 3.0)));
   }
 
@@ -208,7 +210,7 @@ mod tests {
   fn construct_stripe_pattern() {
     let pattern = Pattern::stripe(Colour::WHITE, Colour::BLACK);
     let _expected = Pattern {
-      transform: Matrix4x4::IDENTITY,
+      transform: Transform::IDENTITY,
       pattern: PatternType::Stripe(Colour::WHITE, Colour::BLACK),
     };
     assert!(matches!(pattern, _expected));
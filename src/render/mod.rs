@@ -1,16 +1,29 @@
+pub mod aov;
+pub mod background;
 pub mod camera;
 pub mod canvas;
 pub mod colour;
+pub mod debug_view;
+pub mod integrator;
 pub mod light;
 pub mod material;
+pub mod netpbm;
 pub mod object;
+pub mod path_tracer;
 pub mod ray;
+pub mod tone_map;
 pub mod world;
+pub use aov::*;
+pub use background::*;
 pub use camera::*;
 pub use canvas::*;
 pub use colour::*;
+pub use debug_view::*;
+pub use integrator::*;
 pub use light::*;
 pub use material::*;
 pub use object::*;
+pub use path_tracer::*;
 pub use ray::*;
+pub use tone_map::*;
 pub use world::*;
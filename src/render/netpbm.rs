@@ -0,0 +1,405 @@
+use crate::*;
+use std::io::{self, BufRead, Read, Write};
+
+/// Longest line allowed in plain (P3) PPM files
+const MAX_LINE_LENGTH: usize = 70;
+
+impl Canvas {
+  /// Writes the canvas as a plain (ASCII, P3) PPM image with 8 bits per channel
+  pub fn write_ppm(&self, mut writer: impl Write, tone_mapping: &ToneMapping) -> io::Result<()> {
+    write!(writer, "P3\n{} {}\n255\n", self.width, self.height)?;
+    for y in 0..self.height {
+      let mut line_length = 0;
+      for x in 0..self.width {
+        for value in tone_mapping.to_rgb8(self.pixel_at(x, y)).0 {
+          let value = value.to_string();
+          if line_length + 1 + value.len() >= MAX_LINE_LENGTH {
+            writer.write_all(b"\n")?;
+            line_length = 0;
+          } else if line_length > 0 {
+            writer.write_all(b" ")?;
+            line_length += 1;
+          }
+          writer.write_all(value.as_bytes())?;
+          line_length += value.len();
+        }
+      }
+      writer.write_all(b"\n")?;
+    }
+    writer.flush()
+  }
+
+  /// Writes the canvas as a raw (binary, P6) PPM image with 8 bits per channel
+  pub fn write_ppm_binary(
+    &self,
+    mut writer: impl Write,
+    tone_mapping: &ToneMapping,
+  ) -> io::Result<()> {
+    write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+    for colour in &self.canvas {
+      writer.write_all(&tone_mapping.to_rgb8(*colour).0)?;
+    }
+    writer.flush()
+  }
+
+  /// Writes the canvas as a colour Portable Float Map, keeping the full 32-bit float values
+  pub fn write_pfm(&self, mut writer: impl Write) -> io::Result<()> {
+    // A negative scale marks little-endian data
+    write!(writer, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+    // Rows are stored bottom to top
+    for y in (0..self.height).rev() {
+      for x in 0..self.width {
+        let colour = self.pixel_at(x, y);
+        for value in [colour.red, colour.green, colour.blue] {
+          writer.write_all(&value.to_le_bytes())?;
+        }
+      }
+    }
+    writer.flush()
+  }
+
+  /// Reads a plain (P3) or raw (P6) PPM image, with any maximum value up to 65535. Values are
+  /// decoded with `transfer`, the function used when the image was written.
+  pub fn from_ppm(mut reader: impl Read, transfer: TransferFunction) -> io::Result<Self> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let mut header = Header::new(&bytes);
+
+    let magic = header.token()?;
+    let (width, height) = (header.number()?, header.number()?);
+    let max_value = header.number()?;
+    if max_value == 0 || max_value > u16::MAX as usize {
+      return Err(invalid_data(format!("invalid maximum value {max_value}")));
+    }
+
+    let count = value_count(width, height, 3)?;
+    let values: Vec<usize> = match magic {
+      b"P3" => (0..count)
+        .map(|_| header.number())
+        .collect::<io::Result<_>>()?,
+      b"P6" => {
+        // A single whitespace character separates the header from the pixel data
+        let data = &bytes[(header.position + 1).min(bytes.len())..];
+        let bytes_per_value = if max_value < 256 { 1 } else { 2 };
+        let length = count.checked_mul(bytes_per_value).ok_or_else(too_large)?;
+        if data.len() < length {
+          return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        data[..length]
+          .chunks(bytes_per_value)
+          .map(|value| value.iter().fold(0, |sum, &byte| sum << 8 | byte as usize))
+          .collect()
+      }
+      _ => return Err(invalid_data("not a P3 or P6 PPM image")),
+    };
+    if let Some(value) = values.iter().find(|&&value| value > max_value) {
+      return Err(invalid_data(format!(
+        "value {value} is above the maximum value {max_value}"
+      )));
+    }
+
+    let mut canvas = Canvas::new(width, height);
+    let scale = 1.0 / max_value as f32;
+    for (pixel, rgb) in canvas.canvas.iter_mut().zip(values.chunks(3)) {
+      let colour = Colour::new(rgb[0] as f32, rgb[1] as f32, rgb[2] as f32) * scale;
+      *pixel = transfer.decode(colour);
+    }
+    Ok(canvas)
+  }
+
+  /// Reads a colour (`PF`) or greyscale (`Pf`) Portable Float Map
+  pub fn from_pfm(reader: impl Read) -> io::Result<Self> {
+    let mut reader = io::BufReader::new(reader);
+    let mut line = || -> io::Result<String> {
+      let mut line = String::new();
+      reader.read_line(&mut line)?;
+      Ok(line.trim().to_owned())
+    };
+
+    let channels = match line()?.as_str() {
+      "PF" => 3,
+      "Pf" => 1,
+      _ => return Err(invalid_data("not a PFM image")),
+    };
+    let dimensions = line()?;
+    let mut dimensions = dimensions.split_whitespace().map(str::parse::<usize>);
+    let (Some(Ok(width)), Some(Ok(height))) = (dimensions.next(), dimensions.next()) else {
+      return Err(invalid_data("invalid PFM dimensions"));
+    };
+    // The sign of the scale gives the byte order, negative for little-endian
+    let scale: f32 = line()?
+      .parse()
+      .map_err(|_| invalid_data("invalid PFM scale"))?;
+
+    // Check the length against the data that's actually there before trusting the header
+    let length = value_count(width, height, channels)?
+      .checked_mul(4)
+      .ok_or_else(too_large)?;
+    let mut data = Vec::new();
+    reader.take(length as u64).read_to_end(&mut data)?;
+    if data.len() < length {
+      return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let values: Vec<f32> = data
+      .chunks(4)
+      .map(|bytes| {
+        let bytes = bytes.try_into().unwrap();
+        if scale < 0.0 {
+          f32::from_le_bytes(bytes)
+        } else {
+          f32::from_be_bytes(bytes)
+        }
+      })
+      .collect();
+
+    let mut canvas = Canvas::new(width, height);
+    // Rows are stored bottom to top
+    for (index, pixel) in values.chunks(channels).enumerate() {
+      let (x, y) = (index % width, height - 1 - index / width);
+      let colour = match pixel {
+        [red, green, blue] => Colour::new(*red, *green, *blue),
+        [grey] => Colour::new(*grey, *grey, *grey),
+        _ => unreachable!(),
+      };
+      canvas.write_pixel(x, y, colour);
+    }
+    Ok(canvas)
+  }
+}
+
+/// Whitespace separated header fields of a Netpbm file, skipping `#` comments
+struct Header<'a> {
+  bytes: &'a [u8],
+  position: usize,
+}
+
+impl<'a> Header<'a> {
+  fn new(bytes: &'a [u8]) -> Self {
+    Header { bytes, position: 0 }
+  }
+
+  fn token(&mut self) -> io::Result<&'a [u8]> {
+    loop {
+      match self.bytes.get(self.position) {
+        Some(byte) if byte.is_ascii_whitespace() => self.position += 1,
+        Some(b'#') => {
+          while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|&byte| byte != b'\n')
+          {
+            self.position += 1;
+          }
+        }
+        Some(_) => break,
+        None => return Err(io::ErrorKind::UnexpectedEof.into()),
+      }
+    }
+
+    let start = self.position;
+    while self
+      .bytes
+      .get(self.position)
+      .is_some_and(|byte| !byte.is_ascii_whitespace() && *byte != b'#')
+    {
+      self.position += 1;
+    }
+    Ok(&self.bytes[start..self.position])
+  }
+
+  fn number(&mut self) -> io::Result<usize> {
+    let token = self.token()?;
+    std::str::from_utf8(token)
+      .ok()
+      .and_then(|token| token.parse().ok())
+      .ok_or_else(|| invalid_data(format!("expected a number, found {token:?}")))
+  }
+}
+
+/// Number of values in an image with the given dimensions, or an error if it overflows
+fn value_count(width: usize, height: usize, channels: usize) -> io::Result<usize> {
+  width
+    .checked_mul(height)
+    .and_then(|pixels| pixels.checked_mul(channels))
+    .ok_or_else(too_large)
+}
+
+fn too_large() -> io::Error {
+  invalid_data("image dimensions are too large")
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn canvas() -> Canvas {
+    let mut canvas = Canvas::new(3, 2);
+    canvas.write_pixel(0, 0, Colour::RED);
+    canvas.write_pixel(1, 0, (0.0, 0.2, 0.4).into());
+    canvas.write_pixel(2, 1, (1.0, 0.6, 0.8).into());
+    canvas
+  }
+
+  /// Checks that the canvases match to within 8-bit precision in the encoded values
+  fn assert_round_trip(result: &Canvas, expected: &Canvas, transfer: TransferFunction) {
+    assert_eq!(
+      (result.width, result.height),
+      (expected.width, expected.height)
+    );
+    for (result, expected) in result.canvas.iter().zip(&expected.canvas) {
+      let difference = transfer.encode(*result) - transfer.encode(*expected);
+      let error = difference
+        .red
+        .abs()
+        .max(difference.green.abs())
+        .max(difference.blue.abs());
+      assert!(error <= 0.5 / 255.0, "{result:?} != {expected:?}");
+    }
+  }
+
+  #[test]
+  fn binary_ppm_layout() {
+    let mut bytes = Vec::new();
+    canvas()
+      .write_ppm_binary(&mut bytes, &ToneMapping::LEGACY)
+      .unwrap();
+
+    let header = b"P6\n3 2\n255\n";
+    assert_eq!(&bytes[..header.len()], header);
+    assert_eq!(&bytes[header.len()..][..6], &[255, 0, 0, 0, 51, 102]);
+    assert_eq!(bytes.len(), header.len() + 3 * 2 * 3);
+  }
+
+  #[test]
+  fn ppm_round_trip() {
+    let canvas = canvas();
+    for tone_mapping in [ToneMapping::default(), ToneMapping::LEGACY] {
+      let mut plain = Vec::new();
+      canvas.write_ppm(&mut plain, &tone_mapping).unwrap();
+      let result = Canvas::from_ppm(plain.as_slice(), tone_mapping.transfer).unwrap();
+      assert_round_trip(&result, &canvas, tone_mapping.transfer);
+
+      let mut binary = Vec::new();
+      canvas.write_ppm_binary(&mut binary, &tone_mapping).unwrap();
+      let result = Canvas::from_ppm(binary.as_slice(), tone_mapping.transfer).unwrap();
+      assert_round_trip(&result, &canvas, tone_mapping.transfer);
+    }
+  }
+
+  #[test]
+  fn read_ppm_with_comments_and_max_value() {
+    let ppm = "P3 # plain\n# size\n2 1\n# max\n15\n15 0 3 # first\n0 15\n# last\n0\n";
+    let canvas = Canvas::from_ppm(ppm.as_bytes(), TransferFunction::Linear).unwrap();
+    assert!(canvas.pixel_at(0, 0).approx_eq(Colour::new(1.0, 0.0, 0.2)));
+    assert!(canvas.pixel_at(1, 0).approx_eq(Colour::GREEN));
+  }
+
+  #[test]
+  fn read_16_bit_binary_ppm() {
+    let mut ppm = b"P6\n# comment\n1 1\n1023\n".to_vec();
+    ppm.extend([0x03, 0xff, 0x00, 0x00, 0x01, 0xff]);
+    let canvas = Canvas::from_ppm(ppm.as_slice(), TransferFunction::Linear).unwrap();
+    assert!(canvas
+      .pixel_at(0, 0)
+      .approx_eq(Colour::new(1.0, 0.0, 511.0 / 1023.0)));
+  }
+
+  #[test]
+  fn read_invalid_ppm() {
+    let result = Canvas::from_ppm(b"P5\n1 1\n255\n\0".as_slice(), TransferFunction::Srgb);
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    let result = Canvas::from_ppm(b"P6\n2 2\n255\n\0\0\0".as_slice(), TransferFunction::Srgb);
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    let result = Canvas::from_ppm(b"P3\n1 1\n255\n0 x 0".as_slice(), TransferFunction::Srgb);
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn read_ppm_with_values_above_maximum() {
+    let result = Canvas::from_ppm(b"P3\n1 1\n255\n300 0 0".as_slice(), TransferFunction::Srgb);
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+    let mut ppm = b"P6\n1 1\n1023\n".to_vec();
+    ppm.extend([0x04, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    let result = Canvas::from_ppm(ppm.as_slice(), TransferFunction::Srgb);
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn read_ppm_with_oversized_header() {
+    let result = Canvas::from_ppm(
+      b"P6\n100000 100000\n255\n\0\0\0".as_slice(),
+      TransferFunction::Srgb,
+    );
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    let result = Canvas::from_ppm(
+      b"P3\n100000 100000\n255\n0 0 0".as_slice(),
+      TransferFunction::Srgb,
+    );
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+    let huge = format!("P6\n{} {}\n65535\n", usize::MAX / 2, 3);
+    let result = Canvas::from_ppm(huge.as_bytes(), TransferFunction::Srgb);
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    let huge = format!("P3\n{} {}\n255\n", usize::MAX, usize::MAX);
+    let result = Canvas::from_ppm(huge.as_bytes(), TransferFunction::Srgb);
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn read_pfm_with_oversized_header() {
+    let mut pfm = b"PF\n100000 100000\n-1.0\n".to_vec();
+    pfm.extend(1.0f32.to_le_bytes());
+    let result = Canvas::from_pfm(pfm.as_slice());
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+    let huge = format!("Pf\n{} {}\n-1.0\n", usize::MAX / 2, 3);
+    let result = Canvas::from_pfm(huge.as_bytes());
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn pfm_layout() {
+    let mut canvas = Canvas::new(3, 2);
+    canvas.write_pixel(0, 0, (4.0, 0.5, 0.25).into());
+    canvas.write_pixel(2, 1, (0.0, 12.0, 1.0).into());
+    let mut bytes = Vec::new();
+    canvas.write_pfm(&mut bytes).unwrap();
+
+    let header = b"PF\n3 2\n-1.0\n";
+    assert_eq!(&bytes[..header.len()], header);
+    let floats: Vec<f32> = bytes[header.len()..]
+      .chunks(4)
+      .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+      .collect();
+    assert_eq!(floats.len(), 3 * 2 * 3);
+    // The bottom row comes first
+    assert_eq!(&floats[6..9], &[0.0, 12.0, 1.0]);
+    assert_eq!(&floats[9..12], &[4.0, 0.5, 0.25]);
+  }
+
+  #[test]
+  fn pfm_round_trip() {
+    let mut canvas = canvas();
+    canvas.write_pixel(1, 1, (12.0, -0.5, 1e-3).into());
+    let mut bytes = Vec::new();
+    canvas.write_pfm(&mut bytes).unwrap();
+
+    let result = Canvas::from_pfm(bytes.as_slice()).unwrap();
+    assert_eq!(result.canvas, canvas.canvas);
+  }
+
+  #[test]
+  fn read_big_endian_greyscale_pfm() {
+    let mut pfm = b"Pf\n2 1\n1.0\n".to_vec();
+    pfm.extend(0.5f32.to_be_bytes());
+    pfm.extend(4.0f32.to_be_bytes());
+    let canvas = Canvas::from_pfm(pfm.as_slice()).unwrap();
+    assert!(canvas.pixel_at(0, 0).approx_eq(Colour::new(0.5, 0.5, 0.5)));
+    assert!(canvas.pixel_at(1, 0).approx_eq(Colour::new(4.0, 4.0, 4.0)));
+  }
+}
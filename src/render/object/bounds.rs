@@ -0,0 +1,297 @@
+use crate::*;
+
+/// An axis-aligned bounding box
+#[derive(Debug, Clone, Copy)]
+pub struct Bounds {
+  pub min: Point,
+  pub max: Point,
+}
+
+impl Bounds {
+  /// Bounds containing nothing, which grow to fit whatever is added to them
+  pub const EMPTY: Self = Bounds {
+    min: Point {
+      x: f32::INFINITY,
+      y: f32::INFINITY,
+      z: f32::INFINITY,
+    },
+    max: Point {
+      x: f32::NEG_INFINITY,
+      y: f32::NEG_INFINITY,
+      z: f32::NEG_INFINITY,
+    },
+  };
+
+  /// Bounds spanning -1 to 1 on every axis
+  pub const UNIT: Self = Bounds {
+    min: Point {
+      x: -1.0,
+      y: -1.0,
+      z: -1.0,
+    },
+    max: Point {
+      x: 1.0,
+      y: 1.0,
+      z: 1.0,
+    },
+  };
+
+  pub fn new(min: impl Into<Point>, max: impl Into<Point>) -> Self {
+    Bounds {
+      min: min.into(),
+      max: max.into(),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+  }
+
+  /// Returns whether the bounds extend to infinity in any direction
+  pub fn is_infinite(&self) -> bool {
+    [
+      self.min.x, self.min.y, self.min.z, self.max.x, self.max.y, self.max.z,
+    ]
+    .iter()
+    .any(|value| value.is_infinite())
+  }
+
+  pub fn centre(&self) -> Point {
+    Point::new(
+      (self.min.x + self.max.x) / 2.0,
+      (self.min.y + self.max.y) / 2.0,
+      (self.min.z + self.max.z) / 2.0,
+    )
+  }
+
+  pub fn add_point(&mut self, point: Point) {
+    self.min = Point::new(
+      self.min.x.min(point.x),
+      self.min.y.min(point.y),
+      self.min.z.min(point.z),
+    );
+    self.max = Point::new(
+      self.max.x.max(point.x),
+      self.max.y.max(point.y),
+      self.max.z.max(point.z),
+    );
+  }
+
+  /// Returns the smallest bounds containing both bounds
+  pub fn merge(self, rhs: Bounds) -> Bounds {
+    if rhs.is_empty() {
+      return self;
+    }
+    let mut bounds = self;
+    bounds.add_point(rhs.min);
+    bounds.add_point(rhs.max);
+    bounds
+  }
+
+  pub fn contains_point(&self, point: Point) -> bool {
+    (self.min.x..=self.max.x).contains(&point.x)
+      && (self.min.y..=self.max.y).contains(&point.y)
+      && (self.min.z..=self.max.z).contains(&point.z)
+  }
+
+  pub fn contains_bounds(&self, rhs: Bounds) -> bool {
+    self.contains_point(rhs.min) && self.contains_point(rhs.max)
+  }
+
+  /// Returns the axis-aligned bounds of these bounds after they've been transformed.
+  ///
+  /// Each axis of the result is worked out from the range each matrix element scales its input
+  /// axis over, which avoids multiplying infinite extents by zero.
+  pub fn transform(&self, transform: &Matrix4x4) -> Bounds {
+    if self.is_empty() {
+      return *self;
+    }
+    let min = [self.min.x, self.min.y, self.min.z];
+    let max = [self.max.x, self.max.y, self.max.z];
+
+    let mut new_min = [0.0; 3];
+    let mut new_max = [0.0; 3];
+    for row in 0..3 {
+      new_min[row] = transform[row][3];
+      new_max[row] = transform[row][3];
+      for column in 0..3 {
+        let scale = transform[row][column];
+        if scale == 0.0 {
+          continue;
+        }
+        let (a, b) = (scale * min[column], scale * max[column]);
+        new_min[row] += a.min(b);
+        new_max[row] += a.max(b);
+      }
+    }
+
+    Bounds::new(
+      (new_min[0], new_min[1], new_min[2]),
+      (new_max[0], new_max[1], new_max[2]),
+    )
+  }
+
+  /// Returns whether the ray passes through the bounds
+  pub fn intersects(&self, ray: Ray) -> bool {
+    count_intersection_test();
+    if self.is_empty() {
+      return false;
+    }
+    let (x_t_min, x_t_max) = check_axis(ray.origin.x, ray.direction.x, self.min.x, self.max.x);
+    let (y_t_min, y_t_max) = check_axis(ray.origin.y, ray.direction.y, self.min.y, self.max.y);
+    let (z_t_min, z_t_max) = check_axis(ray.origin.z, ray.direction.z, self.min.z, self.max.z);
+
+    let t_min = x_t_min.max(y_t_min).max(z_t_min);
+    let t_max = x_t_max.min(y_t_max).min(z_t_max);
+
+    t_min <= t_max && t_max >= 0.0
+  }
+
+  /// Splits the bounds in half along their longest axis
+  pub fn split(&self) -> (Bounds, Bounds) {
+    let (dx, dy, dz) = (
+      self.max.x - self.min.x,
+      self.max.y - self.min.y,
+      self.max.z - self.min.z,
+    );
+    let greatest = dx.max(dy).max(dz);
+
+    let (mut left_max, mut right_min) = (self.max, self.min);
+    if greatest == dx {
+      left_max.x = self.min.x + dx / 2.0;
+      right_min.x = left_max.x;
+    } else if greatest == dy {
+      left_max.y = self.min.y + dy / 2.0;
+      right_min.y = left_max.y;
+    } else {
+      left_max.z = self.min.z + dz / 2.0;
+      right_min.z = left_max.z;
+    }
+
+    (
+      Bounds::new(self.min, left_max),
+      Bounds::new(right_min, self.max),
+    )
+  }
+}
+
+/// Returns the t values where the ray enters and leaves the slab between min and max on one axis
+fn check_axis(origin: f32, direction: f32, min: f32, max: f32) -> (f32, f32) {
+  let t_min = (min - origin) / direction;
+  let t_max = (max - origin) / direction;
+
+  if t_min > t_max {
+    (t_max, t_min)
+  } else {
+    (t_min, t_max)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::consts::{PI, SQRT_2};
+
+  #[test]
+  fn empty_bounds() {
+    let bounds = Bounds::EMPTY;
+    assert!(bounds.is_empty());
+    assert!(!bounds.intersects(Ray::new(Point::ORIGIN, (0.0, 0.0, 1.0))));
+  }
+
+  #[test]
+  fn add_points_to_bounds() {
+    let mut bounds = Bounds::EMPTY;
+    bounds.add_point(Point::new(-5.0, 2.0, 0.0));
+    bounds.add_point(Point::new(7.0, 0.0, -3.0));
+
+    assert!(bounds.min.approx_eq(Point::new(-5.0, 0.0, -3.0)));
+    assert!(bounds.max.approx_eq(Point::new(7.0, 2.0, 0.0)));
+  }
+
+  #[test]
+  fn merge_bounds() {
+    let a = Bounds::new((-5.0, -2.0, 0.0), (7.0, 4.0, 4.0));
+    let b = Bounds::new((8.0, -7.0, -2.0), (14.0, 2.0, 8.0));
+    let merged = a.merge(b);
+
+    assert!(merged.min.approx_eq(Point::new(-5.0, -7.0, -2.0)));
+    assert!(merged.max.approx_eq(Point::new(14.0, 4.0, 8.0)));
+    assert!(merged.merge(Bounds::EMPTY).max.approx_eq(merged.max));
+  }
+
+  #[test]
+  fn bounds_contain_points_and_bounds() {
+    let bounds = Bounds::new((5.0, -2.0, 0.0), (11.0, 4.0, 7.0));
+
+    assert!(bounds.contains_point(Point::new(5.0, -2.0, 0.0)));
+    assert!(bounds.contains_point(Point::new(8.0, 1.0, 3.0)));
+    assert!(!bounds.contains_point(Point::new(3.0, 0.0, 3.0)));
+    assert!(!bounds.contains_point(Point::new(8.0, 1.0, 8.0)));
+    assert!(bounds.contains_bounds(Bounds::new((6.0, -1.0, 1.0), (10.0, 3.0, 6.0))));
+    assert!(!bounds.contains_bounds(Bounds::new((4.0, -3.0, -1.0), (10.0, 3.0, 6.0))));
+  }
+
+  #[test]
+  fn transform_bounds() {
+    let bounds = Bounds::UNIT;
+    let transform = Matrix4x4::rotation_x(PI / 4.0) * Matrix4x4::rotation_y(PI / 4.0);
+    let result = bounds.transform(&transform);
+
+    assert!((result.min - Point::new(-SQRT_2, -1.70711, -1.70711)).magnitude() < 0.0001);
+    assert!((result.max - Point::new(SQRT_2, 1.70711, 1.70711)).magnitude() < 0.0001);
+  }
+
+  #[test]
+  fn transform_infinite_bounds() {
+    let plane = Bounds::new(
+      (f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY),
+      (f32::INFINITY, 0.0, f32::INFINITY),
+    );
+
+    let translated = plane.transform(&Matrix4x4::translation(0.0, 2.0, 0.0));
+    assert!(translated.min.y.approx_eq(2.0));
+    assert!(translated.max.y.approx_eq(2.0));
+
+    let rotated = plane.transform(&Matrix4x4::rotation_z(PI / 4.0));
+    assert_eq!(rotated.min.y, f32::NEG_INFINITY);
+    assert_eq!(rotated.max.x, f32::INFINITY);
+  }
+
+  #[test]
+  fn ray_intersects_bounds() {
+    let bounds = Bounds::new((5.0, -2.0, 0.0), (11.0, 4.0, 7.0));
+    let cases = [
+      ((15.0, 1.0, 2.0), (-1.0, 0.0, 0.0), true),
+      ((-5.0, -1.0, 4.0), (1.0, 0.0, 0.0), true),
+      ((7.0, 6.0, 5.0), (0.0, -1.0, 0.0), true),
+      ((9.0, -5.0, 6.0), (0.0, 1.0, 0.0), true),
+      ((8.0, 2.0, 12.0), (0.0, 0.0, -1.0), true),
+      ((6.0, 0.0, -5.0), (0.0, 0.0, 1.0), true),
+      ((8.0, 1.0, 3.5), (0.0, 0.0, 1.0), true),
+      ((9.0, -1.0, -8.0), (2.0, 4.0, 6.0), false),
+      ((8.0, 3.0, -4.0), (6.0, 2.0, 4.0), false),
+      ((9.0, -1.0, -2.0), (4.0, 6.0, 2.0), false),
+      ((4.0, 0.0, 9.0), (0.0, 0.0, -1.0), false),
+      ((8.0, 6.0, -1.0), (0.0, -1.0, 0.0), false),
+      ((12.0, 5.0, 4.0), (-1.0, 0.0, 0.0), false),
+      ((8.0, 1.0, 10.0), (0.0, 0.0, 1.0), false),
+    ];
+
+    for (origin, direction, expected) in cases {
+      let ray = Ray::new(origin, Vector::from(direction).normalise());
+      assert_eq!(bounds.intersects(ray), expected, "{origin:?} {direction:?}");
+    }
+  }
+
+  #[test]
+  fn split_bounds_along_longest_axis() {
+    let bounds = Bounds::new((-1.0, -2.0, -3.0), (9.0, 5.5, 3.0));
+    let (left, right) = bounds.split();
+
+    assert!(left.min.approx_eq(Point::new(-1.0, -2.0, -3.0)));
+    assert!(left.max.approx_eq(Point::new(4.0, 5.5, 3.0)));
+    assert!(right.min.approx_eq(Point::new(4.0, -2.0, -3.0)));
+    assert!(right.max.approx_eq(Point::new(9.0, 5.5, 3.0)));
+  }
+}
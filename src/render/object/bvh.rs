@@ -0,0 +1,216 @@
+use crate::*;
+
+/// The most objects a leaf of the hierarchy holds before it is split
+const MAX_LEAF_SIZE: usize = 4;
+
+/// A bounding volume hierarchy over a list of objects, used to skip testing rays against objects
+/// whose bounds they miss.
+///
+/// The hierarchy stores indices into the list it was built from, so it must be rebuilt whenever
+/// that list changes.
+#[derive(Debug)]
+pub struct Bvh {
+  root: Option<BvhNode>,
+  /// Objects with infinite bounds, such as planes, which are always tested
+  unbounded: Vec<usize>,
+  len: usize,
+}
+
+#[derive(Debug)]
+enum BvhNode {
+  Leaf {
+    bounds: Bounds,
+    objects: Vec<usize>,
+  },
+  Branch {
+    bounds: Bounds,
+    children: Box<[BvhNode; 2]>,
+  },
+}
+
+impl BvhNode {
+  fn bounds(&self) -> &Bounds {
+    match self {
+      BvhNode::Leaf { bounds, .. } | BvhNode::Branch { bounds, .. } => bounds,
+    }
+  }
+
+  /// Builds a node over the given objects, splitting them at the median centre along the axis
+  /// where their centres are most spread out
+  fn build(mut entries: Vec<(usize, Bounds)>) -> BvhNode {
+    let bounds = entries
+      .iter()
+      .fold(Bounds::EMPTY, |acc, (_, bounds)| acc.merge(*bounds));
+
+    let mut centres = Bounds::EMPTY;
+    for (_, bounds) in &entries {
+      centres.add_point(bounds.centre());
+    }
+    let extent = centres.max - centres.min;
+
+    if entries.len() <= MAX_LEAF_SIZE || extent.magnitude() == 0.0 {
+      return BvhNode::Leaf {
+        bounds,
+        objects: entries.into_iter().map(|(index, _)| index).collect(),
+      };
+    }
+
+    let axis = |point: Point| {
+      if extent.x >= extent.y && extent.x >= extent.z {
+        point.x
+      } else if extent.y >= extent.z {
+        point.y
+      } else {
+        point.z
+      }
+    };
+    entries.sort_by(|(_, a), (_, b)| axis(a.centre()).total_cmp(&axis(b.centre())));
+    let right = entries.split_off(entries.len() / 2);
+
+    BvhNode::Branch {
+      bounds,
+      children: Box::new([BvhNode::build(entries), BvhNode::build(right)]),
+    }
+  }
+}
+
+impl Bvh {
+  pub fn build(objects: &[Box<dyn Object>]) -> Self {
+    let mut bounded = vec![];
+    let mut unbounded = vec![];
+    for (index, object) in objects.iter().enumerate() {
+      let bounds = object.bounds();
+      if bounds.is_infinite() {
+        unbounded.push(index);
+      } else if !bounds.is_empty() {
+        bounded.push((index, bounds));
+      }
+    }
+
+    Bvh {
+      root: (!bounded.is_empty()).then(|| BvhNode::build(bounded)),
+      unbounded,
+      len: objects.len(),
+    }
+  }
+
+  /// The number of objects in the list the hierarchy was built from
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns the bounds of every object in the hierarchy
+  pub fn bounds(&self, objects: &[Box<dyn Object>]) -> Bounds {
+    let bounds = self
+      .root
+      .as_ref()
+      .map_or(Bounds::EMPTY, |root| *root.bounds());
+    self
+      .unbounded
+      .iter()
+      .fold(bounds, |acc, &index| acc.merge(objects[index].bounds()))
+  }
+
+  /// Intersects the ray with the objects, which must be the list the hierarchy was built from
+  pub fn intersect<'a>(
+    &self,
+    objects: &'a [Box<dyn Object>],
+    ray: Ray,
+  ) -> IntersectionCollection<'a> {
+    let mut intersections = vec![];
+    let mut test = |index: usize| intersections.extend(objects[index].intersect(ray));
+
+    self.unbounded.iter().for_each(|&index| test(index));
+
+    let mut stack: Vec<&BvhNode> = self.root.iter().collect();
+    while let Some(node) = stack.pop() {
+      if !node.bounds().intersects(ray) {
+        continue;
+      }
+      match node {
+        BvhNode::Leaf { objects, .. } => objects.iter().for_each(|&index| test(index)),
+        BvhNode::Branch { children, .. } => stack.extend(children.iter()),
+      }
+    }
+
+    IntersectionCollection::from_vec(intersections)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn grid_of_spheres() -> Vec<Box<dyn Object>> {
+    let mut objects: Vec<Box<dyn Object>> = vec![];
+    for x in -5..5 {
+      for y in -5..5 {
+        let mut sphere = Sphere::new();
+        sphere.transform = Transform::new(
+          Matrix4x4::translation(x as f32 * 3.0, y as f32 * 3.0, 0.0)
+            * Matrix4x4::scale(0.5, 0.5, 0.5),
+        );
+        objects.push(Box::new(sphere));
+      }
+    }
+    objects
+  }
+
+  #[test]
+  fn bvh_matches_testing_every_object() {
+    let objects = grid_of_spheres();
+    let bvh = Bvh::build(&objects);
+
+    for (x, y) in [
+      (0.0, 0.0),
+      (3.0, -6.0),
+      (1.5, 1.5),
+      (-15.0, 12.0),
+      (0.25, 0.25),
+    ] {
+      let ray = Ray::new((x, y, -5.0), (0.0, 0.0, 1.0));
+      let result = bvh.intersect(&objects, ray);
+      let mut expected = IntersectionCollection::new();
+      for object in &objects {
+        expected.merge(object.intersect(ray));
+      }
+
+      assert_eq!(result.len(), expected.len());
+      for index in 0..result.len() {
+        assert!(result[index].t.approx_eq(expected[index].t));
+        assert!(std::ptr::addr_eq(
+          result[index].object,
+          expected[index].object
+        ));
+      }
+    }
+  }
+
+  #[test]
+  fn bvh_includes_unbounded_objects() {
+    let mut objects = grid_of_spheres();
+    objects.push(Box::new(Plane::new()));
+    let bvh = Bvh::build(&objects);
+
+    let ray = Ray::new((100.0, 5.0, 100.0), (0.0, -1.0, 0.0));
+    let intersections = bvh.intersect(&objects, ray);
+    assert_eq!(intersections.len(), 1);
+    assert!(bvh.bounds(&objects).is_infinite());
+  }
+
+  #[test]
+  fn bvh_bounds_contain_every_object() {
+    let objects = grid_of_spheres();
+    let bvh = Bvh::build(&objects);
+    let bounds = bvh.bounds(&objects);
+
+    assert_eq!(bvh.len(), 100);
+    for object in &objects {
+      assert!(bounds.contains_bounds(object.bounds()));
+    }
+  }
+}
@@ -0,0 +1,190 @@
+use super::cylinder::check_cap;
+use crate::*;
+
+/// A double-napped cone around the Y axis with its apex at the origin, optionally truncated
+/// between `minimum` and `maximum`
+#[derive(Debug)]
+pub struct Cone {
+  pub material: Material,
+  pub transform: Transform,
+  pub minimum: f32,
+  pub maximum: f32,
+  /// Whether the ends of a truncated cone are capped
+  pub closed: bool,
+}
+
+impl Cone {
+  pub fn new() -> Self {
+    Cone {
+      material: Material::default(),
+      transform: Transform::IDENTITY,
+      minimum: f32::NEG_INFINITY,
+      maximum: f32::INFINITY,
+      closed: false,
+    }
+  }
+
+  /// Inserts the intersections with the end caps, if the cone is closed
+  fn intersect_caps<'a>(&'a self, ray: Ray, intersections: &mut IntersectionCollection<'a>) {
+    if !self.closed || ray.direction.y.approx_eq(0.0) {
+      return;
+    }
+
+    // the radius of a cone's cap is the absolute value of its y
+    for y in [self.minimum, self.maximum] {
+      let t = (y - ray.origin.y) / ray.direction.y;
+      if check_cap(ray, t, y.abs()) {
+        intersections.insert(Intersection::new(t, self));
+      }
+    }
+  }
+}
+
+impl Object for Cone {
+  fn local_intersect(&self, ray: Ray) -> IntersectionCollection<'_> {
+    let mut intersections = IntersectionCollection::new();
+    let (o, d) = (ray.origin, ray.direction);
+
+    let a = d.x * d.x - d.y * d.y + d.z * d.z;
+    let b = 2.0 * o.x * d.x - 2.0 * o.y * d.y + 2.0 * o.z * d.z;
+    let c = o.x * o.x - o.y * o.y + o.z * o.z;
+
+    if a.approx_eq(0.0) {
+      // the ray is parallel to one of the cone's halves, so it can only hit the other one
+      if !b.approx_eq(0.0) {
+        let t = -c / (2.0 * b);
+        let y = o.y + t * d.y;
+        if self.minimum < y && y < self.maximum {
+          intersections.insert(Intersection::new(t, self));
+        }
+      }
+    } else {
+      let discriminant = b * b - 4.0 * a * c;
+      // tangent rays can come out slightly negative due to rounding
+      if discriminant < 0.0 && !discriminant.approx_eq(0.0) {
+        return intersections;
+      }
+      let root = discriminant.max(0.0).sqrt();
+
+      for t in [-(root + b) / (2.0 * a), (root - b) / (2.0 * a)] {
+        let y = o.y + t * d.y;
+        if self.minimum < y && y < self.maximum {
+          intersections.insert(Intersection::new(t, self));
+        }
+      }
+    }
+
+    self.intersect_caps(ray, &mut intersections);
+    intersections
+  }
+
+  fn local_normal_at(&self, point: Point, _: &Intersection) -> Vector {
+    let distance = point.x * point.x + point.z * point.z;
+
+    if distance < point.y * point.y && point.y >= self.maximum - EPSILON {
+      Vector::new(0.0, 1.0, 0.0)
+    } else if distance < point.y * point.y && point.y <= self.minimum + EPSILON {
+      Vector::new(0.0, -1.0, 0.0)
+    } else {
+      let y = distance.sqrt();
+      Vector::new(point.x, if point.y > 0.0 { -y } else { y }, point.z)
+    }
+  }
+
+  fn local_bounds(&self) -> Bounds {
+    let radius = self.minimum.abs().max(self.maximum.abs());
+    Bounds::new(
+      (-radius, self.minimum, -radius),
+      (radius, self.maximum, radius),
+    )
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Transform) {
+    self.transform = transform;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ray_strikes_cone() {
+    let cone = Cone::new();
+    let cases = [
+      ((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), 5.0, 5.0),
+      ((0.0, 0.0, -5.0), (1.0, 1.0, 1.0), 8.66025, 8.66025),
+      ((1.0, 1.0, -5.0), (-0.5, -1.0, 1.0), 4.55006, 49.44994),
+    ];
+
+    for (origin, direction, t_1, t_2) in cases {
+      let ray = Ray::new(origin, Vector::from(direction).normalise());
+      let intersections = cone.local_intersect(ray);
+      assert_eq!(intersections.len(), 2);
+      assert!((intersections[0].t - t_1).abs() < 0.001);
+      assert!((intersections[1].t - t_2).abs() < 0.001);
+    }
+  }
+
+  #[test]
+  fn ray_parallel_to_one_half_of_cone() {
+    let cone = Cone::new();
+    let ray = Ray::new((0.0, 0.0, -1.0), Vector::new(0.0, 1.0, 1.0).normalise());
+    let intersections = cone.local_intersect(ray);
+
+    assert_eq!(intersections.len(), 1);
+    assert!((intersections[0].t - 0.35355).abs() < 0.0001);
+  }
+
+  #[test]
+  fn intersect_closed_cone_caps() {
+    let mut cone = Cone::new();
+    cone.minimum = -0.5;
+    cone.maximum = 0.5;
+    cone.closed = true;
+    let cases = [
+      ((0.0, 0.0, -5.0), (0.0, 1.0, 0.0), 0),
+      ((0.0, 0.0, -0.25), (0.0, 1.0, 1.0), 2),
+      ((0.0, 0.0, -0.25), (0.0, 1.0, 0.0), 4),
+    ];
+
+    for (origin, direction, count) in cases {
+      let ray = Ray::new(origin, Vector::from(direction).normalise());
+      assert_eq!(cone.local_intersect(ray).len(), count);
+    }
+  }
+
+  #[test]
+  fn cone_normal() {
+    let cone = Cone::new();
+    let cases = [
+      ((0.0, 0.0, 0.0), (0.0, 0.0, 0.0)),
+      ((1.0, 1.0, 1.0), (1.0, -(2.0f32.sqrt()), 1.0)),
+      ((-1.0, -1.0, 0.0), (-1.0, 1.0, 0.0)),
+    ];
+
+    for (point, expected) in cases {
+      let normal = cone.local_normal_at(point.into(), &Intersection::new(0.0, &cone));
+      assert!(normal.approx_eq(expected.into()));
+    }
+  }
+
+  #[test]
+  fn truncated_cone_bounds() {
+    let mut cone = Cone::new();
+    cone.minimum = -5.0;
+    cone.maximum = 3.0;
+    let bounds = cone.local_bounds();
+
+    assert!(bounds.min.approx_eq(Point::new(-5.0, -5.0, -5.0)));
+    assert!(bounds.max.approx_eq(Point::new(5.0, 3.0, 5.0)));
+  }
+}
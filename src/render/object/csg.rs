@@ -0,0 +1,297 @@
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOperation {
+  Union,
+  Intersection,
+  Difference,
+}
+
+impl CsgOperation {
+  /// Returns whether an intersection is on the surface of the combined shape, given whether it
+  /// hit the left shape, and whether it is inside the left and right shapes
+  pub fn intersection_allowed(self, left_hit: bool, inside_left: bool, inside_right: bool) -> bool {
+    match self {
+      CsgOperation::Union => (left_hit && !inside_right) || (!left_hit && !inside_left),
+      CsgOperation::Intersection => (left_hit && inside_right) || (!left_hit && inside_left),
+      CsgOperation::Difference => (left_hit && !inside_right) || (!left_hit && inside_left),
+    }
+  }
+}
+
+/// Constructive solid geometry combining two objects.
+///
+/// Like a Group, the transform of the Csg is applied on top of the transforms of both operands.
+#[derive(Debug)]
+pub struct Csg {
+  operation: CsgOperation,
+  left: Box<dyn Object>,
+  right: Box<dyn Object>,
+  /// Transforms of the operands relative to the Csg, from before the Csg's was applied
+  operand_transforms: (Transform, Transform),
+  transform: Transform,
+  material: Material,
+  bounds: Bounds,
+}
+
+impl Csg {
+  pub fn new(operation: CsgOperation, left: Box<dyn Object>, right: Box<dyn Object>) -> Self {
+    Csg {
+      operation,
+      bounds: left.bounds().merge(right.bounds()),
+      operand_transforms: (left.transform().clone(), right.transform().clone()),
+      left,
+      right,
+      transform: Transform::IDENTITY,
+      material: Material::default(),
+    }
+  }
+
+  pub fn union(left: Box<dyn Object>, right: Box<dyn Object>) -> Self {
+    Csg::new(CsgOperation::Union, left, right)
+  }
+
+  pub fn intersection(left: Box<dyn Object>, right: Box<dyn Object>) -> Self {
+    Csg::new(CsgOperation::Intersection, left, right)
+  }
+
+  pub fn difference(left: Box<dyn Object>, right: Box<dyn Object>) -> Self {
+    Csg::new(CsgOperation::Difference, left, right)
+  }
+
+  /// Panics if the matrix is not invertible
+  pub fn with_transform(mut self, transform: Matrix4x4) -> Self {
+    self.set_transform(Transform::new(transform));
+    self
+  }
+
+  pub fn operation(&self) -> CsgOperation {
+    self.operation
+  }
+
+  pub fn left(&self) -> &dyn Object {
+    &*self.left
+  }
+
+  pub fn right(&self) -> &dyn Object {
+    &*self.right
+  }
+
+  /// Keeps only the intersections that lie on the surface of the combined shape
+  pub fn filter_intersections<'a>(
+    &self,
+    intersections: IntersectionCollection<'a>,
+  ) -> IntersectionCollection<'a> {
+    let (mut inside_left, mut inside_right) = (false, false);
+    let mut filtered = vec![];
+
+    for index in 0..intersections.len() {
+      let intersection = intersections[index];
+      let left_hit = self.left.includes(intersection.object);
+
+      if self
+        .operation
+        .intersection_allowed(left_hit, inside_left, inside_right)
+      {
+        filtered.push(intersection);
+      }
+
+      if left_hit {
+        inside_left = !inside_left;
+      } else {
+        inside_right = !inside_right;
+      }
+    }
+
+    IntersectionCollection::from_vec_unchecked(filtered)
+  }
+}
+
+impl Object for Csg {
+  /// The operands are already in world space, so the ray is passed to them untransformed
+  fn intersect(&self, ray: Ray) -> IntersectionCollection<'_> {
+    if !self.bounds.intersects(ray) {
+      return IntersectionCollection::new();
+    }
+    let mut intersections = self.left.intersect(ray);
+    intersections.merge(self.right.intersect(ray));
+    self.filter_intersections(intersections)
+  }
+
+  fn local_intersect(&self, ray: Ray) -> IntersectionCollection<'_> {
+    self.intersect(self.transform.matrix() * ray)
+  }
+
+  fn local_normal_at(&self, _: Point, _: &Intersection) -> Vector {
+    unreachable!("a csg has no surface of its own, so it is never the object that is hit")
+  }
+
+  /// Returns the bounds of both operands, which are already in world space
+  fn bounds(&self) -> Bounds {
+    self.bounds
+  }
+
+  fn local_bounds(&self) -> Bounds {
+    self.bounds.transform(self.transform.inverse())
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  /// Replaces the transform, applying it to both operands' own transforms again
+  fn set_transform(&mut self, transform: Transform) {
+    let (left, right) = &self.operand_transforms;
+    self.left.set_transform(&transform * left);
+    self.right.set_transform(&transform * right);
+    self.bounds = self.left.bounds().merge(self.right.bounds());
+    self.transform = transform;
+  }
+
+  fn includes(&self, object: &dyn Object) -> bool {
+    self.left.includes(object) || self.right.includes(object)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn construct_csg() {
+    let csg = Csg::union(Box::new(Sphere::new()), Box::new(Cube::new()));
+
+    assert_eq!(csg.operation(), CsgOperation::Union);
+    assert!(csg.transform().matrix().approx_eq(Matrix4x4::IDENTITY));
+    assert!(csg.includes(csg.left()));
+    assert!(csg.includes(csg.right()));
+  }
+
+  #[test]
+  fn evaluate_csg_rules() {
+    use CsgOperation::*;
+    #[rustfmt::skip]
+    let cases = [
+      (Union, true, true, true, false),
+      (Union, true, true, false, true),
+      (Union, true, false, true, false),
+      (Union, true, false, false, true),
+      (Union, false, true, true, false),
+      (Union, false, true, false, false),
+      (Union, false, false, true, true),
+      (Union, false, false, false, true),
+      (Intersection, true, true, true, true),
+      (Intersection, true, true, false, false),
+      (Intersection, true, false, true, true),
+      (Intersection, true, false, false, false),
+      (Intersection, false, true, true, true),
+      (Intersection, false, true, false, true),
+      (Intersection, false, false, true, false),
+      (Intersection, false, false, false, false),
+      (Difference, true, true, true, false),
+      (Difference, true, true, false, true),
+      (Difference, true, false, true, false),
+      (Difference, true, false, false, true),
+      (Difference, false, true, true, true),
+      (Difference, false, true, false, true),
+      (Difference, false, false, true, false),
+      (Difference, false, false, false, false),
+    ];
+
+    for (operation, left_hit, inside_left, inside_right, expected) in cases {
+      assert_eq!(
+        operation.intersection_allowed(left_hit, inside_left, inside_right),
+        expected
+      );
+    }
+  }
+
+  #[test]
+  fn filter_list_of_intersections() {
+    let cases = [
+      (CsgOperation::Union, 0, 3),
+      (CsgOperation::Intersection, 1, 2),
+      (CsgOperation::Difference, 0, 1),
+    ];
+
+    for (operation, first, second) in cases {
+      let csg = Csg::new(operation, Box::new(Sphere::new()), Box::new(Cube::new()));
+      let intersections = IntersectionCollection::from_vec_unchecked(vec![
+        Intersection::new(1.0, csg.left()),
+        Intersection::new(2.0, csg.right()),
+        Intersection::new(3.0, csg.left()),
+        Intersection::new(4.0, csg.right()),
+      ]);
+      let expected = [intersections[first].t, intersections[second].t];
+
+      let result = csg.filter_intersections(intersections);
+      assert_eq!(result.len(), 2);
+      assert!(result[0].t.approx_eq(expected[0]));
+      assert!(result[1].t.approx_eq(expected[1]));
+    }
+  }
+
+  #[test]
+  fn ray_misses_csg() {
+    let csg = Csg::union(Box::new(Sphere::new()), Box::new(Cube::new()));
+    let ray = Ray::new((0.0, 2.0, -5.0), (0.0, 0.0, 1.0));
+
+    assert!(csg.intersect(ray).is_empty());
+  }
+
+  #[test]
+  fn ray_hits_csg() {
+    let mut right = Sphere::new();
+    right.transform = Transform::new(Matrix4x4::translation(0.0, 0.0, 0.5));
+    let csg = Csg::union(Box::new(Sphere::new()), Box::new(right));
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let intersections = csg.intersect(ray);
+
+    assert_eq!(intersections.len(), 2);
+    assert!(intersections[0].t.approx_eq(4.0));
+    assert!(std::ptr::addr_eq(intersections[0].object, csg.left()));
+    assert!(intersections[1].t.approx_eq(6.5));
+    assert!(std::ptr::addr_eq(intersections[1].object, csg.right()));
+  }
+
+  #[test]
+  fn difference_cuts_hole() {
+    let mut hole = Cylinder::new();
+    hole.transform = Transform::new(Matrix4x4::scale(0.5, 1.0, 0.5));
+    hole.minimum = -2.0;
+    hole.maximum = 2.0;
+    hole.closed = true;
+    let csg = Csg::difference(Box::new(Sphere::new()), Box::new(hole))
+      .with_transform(Matrix4x4::translation(0.0, 0.0, 10.0));
+
+    let through_hole = Ray::new((0.0, -5.0, 10.0), (0.0, 1.0, 0.0));
+    assert!(csg.intersect(through_hole).is_empty());
+
+    let beside_hole = Ray::new((0.75, -5.0, 10.0), (0.0, 1.0, 0.0));
+    assert_eq!(csg.intersect(beside_hole).len(), 2);
+  }
+
+  #[test]
+  fn set_transform_replaces_previous_transform() {
+    let mut right = Sphere::new();
+    right.transform = Transform::new(Matrix4x4::translation(0.0, 0.0, 0.5));
+    let mut csg = Csg::union(Box::new(Sphere::new()), Box::new(right))
+      .with_transform(Matrix4x4::scale(3.0, 3.0, 3.0));
+    csg.set_transform(Transform::new(Matrix4x4::translation(1.0, 0.0, 0.0)));
+
+    assert!(csg
+      .left()
+      .transform()
+      .matrix()
+      .approx_eq(Matrix4x4::translation(1.0, 0.0, 0.0)));
+    assert!(csg
+      .right()
+      .transform()
+      .matrix()
+      .approx_eq(Matrix4x4::translation(1.0, 0.0, 0.5)));
+  }
+}
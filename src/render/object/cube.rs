@@ -0,0 +1,148 @@
+use crate::*;
+
+/// An axis-aligned cube spanning -1 to 1 on every axis
+#[derive(Debug)]
+pub struct Cube {
+  pub material: Material,
+  pub transform: Transform,
+}
+
+impl Cube {
+  pub fn new() -> Self {
+    Cube {
+      material: Material::default(),
+      transform: Transform::IDENTITY,
+    }
+  }
+}
+
+/// Returns the t values where the ray enters and leaves the slab between -1 and 1 on one axis
+fn check_axis(origin: f32, direction: f32) -> (f32, f32) {
+  let t_min_numerator = -1.0 - origin;
+  let t_max_numerator = 1.0 - origin;
+
+  // dividing by zero gives infinities of the correct sign
+  let (t_min, t_max) = (t_min_numerator / direction, t_max_numerator / direction);
+
+  if t_min > t_max {
+    (t_max, t_min)
+  } else {
+    (t_min, t_max)
+  }
+}
+
+impl Object for Cube {
+  fn local_intersect(&self, ray: Ray) -> IntersectionCollection<'_> {
+    let (x_t_min, x_t_max) = check_axis(ray.origin.x, ray.direction.x);
+    let (y_t_min, y_t_max) = check_axis(ray.origin.y, ray.direction.y);
+    let (z_t_min, z_t_max) = check_axis(ray.origin.z, ray.direction.z);
+
+    let t_min = x_t_min.max(y_t_min).max(z_t_min);
+    let t_max = x_t_max.min(y_t_max).min(z_t_max);
+
+    if t_min > t_max {
+      return IntersectionCollection::new();
+    }
+
+    IntersectionCollection::from_vec_unchecked(vec![
+      Intersection::new(t_min, self),
+      Intersection::new(t_max, self),
+    ])
+  }
+
+  /// The normal points along whichever axis the point is furthest out on
+  fn local_normal_at(&self, point: Point, _: &Intersection) -> Vector {
+    let (x, y, z) = (point.x.abs(), point.y.abs(), point.z.abs());
+    let max = x.max(y).max(z);
+
+    if max == x {
+      Vector::new(point.x, 0.0, 0.0)
+    } else if max == y {
+      Vector::new(0.0, point.y, 0.0)
+    } else {
+      Vector::new(0.0, 0.0, point.z)
+    }
+  }
+
+  fn local_bounds(&self) -> Bounds {
+    Bounds::UNIT
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Transform) {
+    self.transform = transform;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ray_intersects_cube() {
+    let cube = Cube::new();
+    let cases = [
+      ((5.0, 0.5, 0.0), (-1.0, 0.0, 0.0), 4.0, 6.0),
+      ((-5.0, 0.5, 0.0), (1.0, 0.0, 0.0), 4.0, 6.0),
+      ((0.5, 5.0, 0.0), (0.0, -1.0, 0.0), 4.0, 6.0),
+      ((0.5, -5.0, 0.0), (0.0, 1.0, 0.0), 4.0, 6.0),
+      ((0.5, 0.0, 5.0), (0.0, 0.0, -1.0), 4.0, 6.0),
+      ((0.5, 0.0, -5.0), (0.0, 0.0, 1.0), 4.0, 6.0),
+      ((0.0, 0.5, 0.0), (0.0, 0.0, 1.0), -1.0, 1.0),
+    ];
+
+    for (origin, direction, t_1, t_2) in cases {
+      let ray = Ray::new(origin, direction);
+      let intersections = cube.local_intersect(ray);
+      assert_eq!(intersections.len(), 2);
+      assert!(intersections[0].t.approx_eq(t_1));
+      assert!(intersections[1].t.approx_eq(t_2));
+    }
+  }
+
+  #[test]
+  fn ray_misses_cube() {
+    let cube = Cube::new();
+    let cases = [
+      ((-2.0, 0.0, 0.0), (0.2673, 0.5345, 0.8018)),
+      ((0.0, -2.0, 0.0), (0.8018, 0.2673, 0.5345)),
+      ((0.0, 0.0, -2.0), (0.5345, 0.8018, 0.2673)),
+      ((2.0, 0.0, 2.0), (0.0, 0.0, -1.0)),
+      ((0.0, 2.0, 2.0), (0.0, -1.0, 0.0)),
+      ((2.0, 2.0, 0.0), (-1.0, 0.0, 0.0)),
+    ];
+
+    for (origin, direction) in cases {
+      let ray = Ray::new(origin, direction);
+      let intersections = cube.local_intersect(ray);
+      assert!(intersections.is_empty());
+    }
+  }
+
+  #[test]
+  fn cube_normal() {
+    let cube = Cube::new();
+    let cases = [
+      ((1.0, 0.5, -0.8), (1.0, 0.0, 0.0)),
+      ((-1.0, -0.2, 0.9), (-1.0, 0.0, 0.0)),
+      ((-0.4, 1.0, -0.1), (0.0, 1.0, 0.0)),
+      ((0.3, -1.0, -0.7), (0.0, -1.0, 0.0)),
+      ((-0.6, 0.3, 1.0), (0.0, 0.0, 1.0)),
+      ((0.4, 0.4, -1.0), (0.0, 0.0, -1.0)),
+      ((1.0, 1.0, 1.0), (1.0, 0.0, 0.0)),
+      ((-1.0, -1.0, -1.0), (-1.0, 0.0, 0.0)),
+    ];
+
+    for (point, expected) in cases {
+      let normal = cube.local_normal_at(point.into(), &Intersection::new(0.0, &cube));
+      assert!(normal.approx_eq(expected.into()));
+    }
+  }
+}
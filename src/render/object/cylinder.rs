@@ -0,0 +1,241 @@
+use crate::*;
+
+/// A cylinder of radius 1 around the Y axis, optionally truncated between `minimum` and `maximum`
+#[derive(Debug)]
+pub struct Cylinder {
+  pub material: Material,
+  pub transform: Transform,
+  pub minimum: f32,
+  pub maximum: f32,
+  /// Whether the ends of a truncated cylinder are capped
+  pub closed: bool,
+}
+
+impl Cylinder {
+  pub fn new() -> Self {
+    Cylinder {
+      material: Material::default(),
+      transform: Transform::IDENTITY,
+      minimum: f32::NEG_INFINITY,
+      maximum: f32::INFINITY,
+      closed: false,
+    }
+  }
+
+  /// Inserts the intersections with the end caps, if the cylinder is closed
+  fn intersect_caps<'a>(&'a self, ray: Ray, intersections: &mut IntersectionCollection<'a>) {
+    if !self.closed || ray.direction.y.approx_eq(0.0) {
+      return;
+    }
+
+    for y in [self.minimum, self.maximum] {
+      let t = (y - ray.origin.y) / ray.direction.y;
+      if check_cap(ray, t, 1.0) {
+        intersections.insert(Intersection::new(t, self));
+      }
+    }
+  }
+}
+
+/// Returns whether the intersection at t is within the given radius of the Y axis
+pub(super) fn check_cap(ray: Ray, t: f32, radius: f32) -> bool {
+  let x = ray.origin.x + t * ray.direction.x;
+  let z = ray.origin.z + t * ray.direction.z;
+
+  x * x + z * z <= radius * radius + EPSILON
+}
+
+impl Object for Cylinder {
+  fn local_intersect(&self, ray: Ray) -> IntersectionCollection<'_> {
+    let mut intersections = IntersectionCollection::new();
+
+    let a = ray.direction.x * ray.direction.x + ray.direction.z * ray.direction.z;
+    // rays parallel to the Y axis can only hit the caps
+    if !a.approx_eq(0.0) {
+      let b = 2.0 * ray.origin.x * ray.direction.x + 2.0 * ray.origin.z * ray.direction.z;
+      let c = ray.origin.x * ray.origin.x + ray.origin.z * ray.origin.z - 1.0;
+
+      let discriminant = b * b - 4.0 * a * c;
+      if discriminant < 0.0 {
+        return intersections;
+      }
+
+      for t in [
+        -(discriminant.sqrt() + b) / (2.0 * a),
+        (discriminant.sqrt() - b) / (2.0 * a),
+      ] {
+        let y = ray.origin.y + t * ray.direction.y;
+        if self.minimum < y && y < self.maximum {
+          intersections.insert(Intersection::new(t, self));
+        }
+      }
+    }
+
+    self.intersect_caps(ray, &mut intersections);
+    intersections
+  }
+
+  fn local_normal_at(&self, point: Point, _: &Intersection) -> Vector {
+    let distance = point.x * point.x + point.z * point.z;
+
+    if distance < 1.0 && point.y >= self.maximum - EPSILON {
+      Vector::new(0.0, 1.0, 0.0)
+    } else if distance < 1.0 && point.y <= self.minimum + EPSILON {
+      Vector::new(0.0, -1.0, 0.0)
+    } else {
+      Vector::new(point.x, 0.0, point.z)
+    }
+  }
+
+  fn local_bounds(&self) -> Bounds {
+    Bounds::new((-1.0, self.minimum, -1.0), (1.0, self.maximum, 1.0))
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Transform) {
+    self.transform = transform;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ray_misses_cylinder() {
+    let cylinder = Cylinder::new();
+    let cases = [
+      ((1.0, 0.0, 0.0), (0.0, 1.0, 0.0)),
+      ((0.0, 0.0, 0.0), (0.0, 1.0, 0.0)),
+      ((0.0, 0.0, -5.0), (1.0, 1.0, 1.0)),
+    ];
+
+    for (origin, direction) in cases {
+      let ray = Ray::new(origin, Vector::from(direction).normalise());
+      assert!(cylinder.local_intersect(ray).is_empty());
+    }
+  }
+
+  #[test]
+  fn ray_strikes_cylinder() {
+    let cylinder = Cylinder::new();
+    let cases = [
+      ((1.0, 0.0, -5.0), (0.0, 0.0, 1.0), 5.0, 5.0),
+      ((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), 4.0, 6.0),
+      ((0.5, 0.0, -5.0), (0.1, 1.0, 1.0), 6.80798, 7.08872),
+    ];
+
+    for (origin, direction, t_1, t_2) in cases {
+      let ray = Ray::new(origin, Vector::from(direction).normalise());
+      let intersections = cylinder.local_intersect(ray);
+      assert_eq!(intersections.len(), 2);
+      assert!((intersections[0].t - t_1).abs() < 0.0001);
+      assert!((intersections[1].t - t_2).abs() < 0.0001);
+    }
+  }
+
+  #[test]
+  fn cylinder_normal() {
+    let cylinder = Cylinder::new();
+    let cases = [
+      ((1.0, 0.0, 0.0), (1.0, 0.0, 0.0)),
+      ((0.0, 5.0, -1.0), (0.0, 0.0, -1.0)),
+      ((0.0, -2.0, 1.0), (0.0, 0.0, 1.0)),
+      ((-1.0, 1.0, 0.0), (-1.0, 0.0, 0.0)),
+    ];
+
+    for (point, expected) in cases {
+      let normal = cylinder.local_normal_at(point.into(), &Intersection::new(0.0, &cylinder));
+      assert!(normal.approx_eq(expected.into()));
+    }
+  }
+
+  #[test]
+  fn default_cylinder_is_infinite_and_open() {
+    let cylinder = Cylinder::new();
+    assert_eq!(cylinder.minimum, f32::NEG_INFINITY);
+    assert_eq!(cylinder.maximum, f32::INFINITY);
+    assert!(!cylinder.closed);
+  }
+
+  #[test]
+  fn intersect_truncated_cylinder() {
+    let mut cylinder = Cylinder::new();
+    cylinder.minimum = 1.0;
+    cylinder.maximum = 2.0;
+    let cases = [
+      ((0.0, 1.5, 0.0), (0.1, 1.0, 0.0), 0),
+      ((0.0, 3.0, -5.0), (0.0, 0.0, 1.0), 0),
+      ((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), 0),
+      ((0.0, 2.0, -5.0), (0.0, 0.0, 1.0), 0),
+      ((0.0, 1.0, -5.0), (0.0, 0.0, 1.0), 0),
+      ((0.0, 1.5, -2.0), (0.0, 0.0, 1.0), 2),
+    ];
+
+    for (origin, direction, count) in cases {
+      let ray = Ray::new(origin, Vector::from(direction).normalise());
+      assert_eq!(cylinder.local_intersect(ray).len(), count);
+    }
+  }
+
+  #[test]
+  fn intersect_closed_cylinder_caps() {
+    let mut cylinder = Cylinder::new();
+    cylinder.minimum = 1.0;
+    cylinder.maximum = 2.0;
+    cylinder.closed = true;
+    let cases = [
+      ((0.0, 3.0, 0.0), (0.0, -1.0, 0.0), 2),
+      ((0.0, 3.0, -2.0), (0.0, -1.0, 2.0), 2),
+      ((0.0, 4.0, -2.0), (0.0, -1.0, 1.0), 2),
+      ((0.0, 0.0, -2.0), (0.0, 1.0, 2.0), 2),
+      ((0.0, -1.0, -2.0), (0.0, 1.0, 1.0), 2),
+    ];
+
+    for (origin, direction, count) in cases {
+      let ray = Ray::new(origin, Vector::from(direction).normalise());
+      let intersections = cylinder.local_intersect(ray);
+      assert_eq!(intersections.len(), count);
+      assert!(intersections[0].t <= intersections[1].t);
+    }
+  }
+
+  #[test]
+  fn closed_cylinder_cap_normal() {
+    let mut cylinder = Cylinder::new();
+    cylinder.minimum = 1.0;
+    cylinder.maximum = 2.0;
+    cylinder.closed = true;
+    let cases = [
+      ((0.0, 1.0, 0.0), (0.0, -1.0, 0.0)),
+      ((0.5, 1.0, 0.0), (0.0, -1.0, 0.0)),
+      ((0.0, 1.0, 0.5), (0.0, -1.0, 0.0)),
+      ((0.0, 2.0, 0.0), (0.0, 1.0, 0.0)),
+      ((0.5, 2.0, 0.0), (0.0, 1.0, 0.0)),
+      ((0.0, 2.0, 0.5), (0.0, 1.0, 0.0)),
+    ];
+
+    for (point, expected) in cases {
+      let normal = cylinder.local_normal_at(point.into(), &Intersection::new(0.0, &cylinder));
+      assert!(normal.approx_eq(expected.into()));
+    }
+  }
+
+  #[test]
+  fn truncated_cylinder_bounds() {
+    let mut cylinder = Cylinder::new();
+    cylinder.minimum = -5.0;
+    cylinder.maximum = 3.0;
+    let bounds = cylinder.local_bounds();
+
+    assert!(bounds.min.approx_eq(Point::new(-1.0, -5.0, -1.0)));
+    assert!(bounds.max.approx_eq(Point::new(1.0, 3.0, 1.0)));
+  }
+}
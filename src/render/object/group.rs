@@ -0,0 +1,331 @@
+use crate::*;
+
+/// A collection of objects that are transformed together.
+///
+/// A group's transform is applied on top of the transforms of its children as they are added, so
+/// the transform of an object inside a group takes it all the way from local space to world space.
+#[derive(Debug)]
+pub struct Group {
+  transform: Transform,
+  material: Material,
+  children: Vec<Box<dyn Object>>,
+  /// Transforms of the children relative to the group, from before the group's was applied
+  child_transforms: Vec<Transform>,
+  bounds: Bounds,
+  bvh: Option<Bvh>,
+}
+
+impl Group {
+  pub fn new() -> Self {
+    Group {
+      transform: Transform::IDENTITY,
+      material: Material::default(),
+      children: vec![],
+      child_transforms: vec![],
+      bounds: Bounds::EMPTY,
+      bvh: None,
+    }
+  }
+
+  /// Panics if the matrix is not invertible
+  pub fn with_transform(mut self, transform: Matrix4x4) -> Self {
+    self.set_transform(Transform::new(transform));
+    self
+  }
+
+  pub fn add_child(&mut self, mut child: Box<dyn Object>) {
+    let child_transform = child.transform().clone();
+    child.set_transform(&self.transform * &child_transform);
+    self.bounds = self.bounds.merge(child.bounds());
+    self.children.push(child);
+    self.child_transforms.push(child_transform);
+    self.bvh = None;
+  }
+
+  /// Builds a bounding volume hierarchy over the children, which is used to speed up intersection
+  /// tests until another child is added
+  pub fn build_bvh(&mut self) {
+    self.bvh = Some(Bvh::build(&self.children));
+  }
+
+  pub fn children(&self) -> &[Box<dyn Object>] {
+    &self.children
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.children.is_empty()
+  }
+}
+
+impl Object for Group {
+  /// The children are already in world space, so the ray is passed to them untransformed
+  fn intersect(&self, ray: Ray) -> IntersectionCollection<'_> {
+    if !self.bounds.intersects(ray) {
+      return IntersectionCollection::new();
+    }
+    if let Some(bvh) = &self.bvh {
+      return bvh.intersect(&self.children, ray);
+    }
+
+    let mut intersections = IntersectionCollection::new();
+    self.children.iter().fold(&mut intersections, |acc, child| {
+      acc.merge(child.intersect(ray))
+    });
+    intersections
+  }
+
+  fn local_intersect(&self, ray: Ray) -> IntersectionCollection<'_> {
+    self.intersect(self.transform.matrix() * ray)
+  }
+
+  fn local_normal_at(&self, _: Point, _: &Intersection) -> Vector {
+    unreachable!("a group has no surface of its own, so it is never the object that is hit")
+  }
+
+  /// Returns the bounds of the children, which are already in world space
+  fn bounds(&self) -> Bounds {
+    self.bounds
+  }
+
+  fn local_bounds(&self) -> Bounds {
+    self.bounds.transform(self.transform.inverse())
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  /// Replaces the group's transform, applying it to the children's own transforms again
+  fn set_transform(&mut self, transform: Transform) {
+    self.bounds = Bounds::EMPTY;
+    for (child, child_transform) in self.children.iter_mut().zip(&self.child_transforms) {
+      child.set_transform(&transform * child_transform);
+      self.bounds = self.bounds.merge(child.bounds());
+    }
+    self.transform = transform;
+    if self.bvh.is_some() {
+      self.build_bvh();
+    }
+  }
+
+  fn includes(&self, object: &dyn Object) -> bool {
+    self.children.iter().any(|child| child.includes(object))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::consts::PI;
+
+  #[test]
+  fn construct_group() {
+    let group = Group::new();
+
+    assert!(group.transform().matrix().approx_eq(Matrix4x4::IDENTITY));
+    assert!(group.is_empty());
+  }
+
+  #[test]
+  fn add_child_to_group() {
+    let mut group = Group::new();
+    group.add_child(Box::new(Sphere::new()));
+
+    assert_eq!(group.children().len(), 1);
+    assert!(group.children()[0]
+      .transform()
+      .matrix()
+      .approx_eq(Matrix4x4::IDENTITY));
+  }
+
+  #[test]
+  fn intersect_empty_group() {
+    let group = Group::new();
+    let ray = Ray::new(Point::ORIGIN, (0.0, 0.0, 1.0));
+
+    assert!(group.local_intersect(ray).is_empty());
+  }
+
+  #[test]
+  fn intersect_nonempty_group() {
+    let mut group = Group::new();
+    let sphere_1 = Sphere::new();
+    let mut sphere_2 = Sphere::new();
+    sphere_2.transform = Transform::new(Matrix4x4::translation(0.0, 0.0, -3.0));
+    let mut sphere_3 = Sphere::new();
+    sphere_3.transform = Transform::new(Matrix4x4::translation(5.0, 0.0, 0.0));
+    group.add_child(Box::new(sphere_1));
+    group.add_child(Box::new(sphere_2));
+    group.add_child(Box::new(sphere_3));
+
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let intersections = group.local_intersect(ray);
+
+    let (sphere_1, sphere_2) = (&*group.children()[0], &*group.children()[1]);
+    assert_eq!(intersections.len(), 4);
+    assert!(std::ptr::addr_eq(intersections[0].object, sphere_2));
+    assert!(std::ptr::addr_eq(intersections[1].object, sphere_2));
+    assert!(std::ptr::addr_eq(intersections[2].object, sphere_1));
+    assert!(std::ptr::addr_eq(intersections[3].object, sphere_1));
+  }
+
+  #[test]
+  fn intersect_transformed_group() {
+    let mut group = Group::new().with_transform(Matrix4x4::scale(2.0, 2.0, 2.0));
+    let mut sphere = Sphere::new();
+    sphere.transform = Transform::new(Matrix4x4::translation(5.0, 0.0, 0.0));
+    group.add_child(Box::new(sphere));
+
+    let ray = Ray::new((10.0, 0.0, -10.0), (0.0, 0.0, 1.0));
+    let intersections = group.intersect(ray);
+
+    assert_eq!(intersections.len(), 2);
+  }
+
+  #[test]
+  fn group_includes_its_descendants() {
+    let mut inner = Group::new();
+    inner.add_child(Box::new(Sphere::new()));
+    let mut outer = Group::new();
+    outer.add_child(Box::new(inner));
+    let other = Sphere::new();
+
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let intersections = outer.intersect(ray);
+    assert!(outer.includes(intersections[0].object));
+    assert!(!outer.includes(&other));
+  }
+
+  #[test]
+  fn set_transform_after_adding_children() {
+    let mut group = Group::new();
+    let mut sphere = Sphere::new();
+    sphere.transform = Transform::new(Matrix4x4::translation(5.0, 0.0, 0.0));
+    group.add_child(Box::new(sphere));
+    group.set_transform(Transform::new(Matrix4x4::scale(2.0, 2.0, 2.0)));
+
+    let expected = Matrix4x4::scale(2.0, 2.0, 2.0) * Matrix4x4::translation(5.0, 0.0, 0.0);
+    assert!(group.children()[0].transform().matrix().approx_eq(expected));
+  }
+
+  #[test]
+  fn set_transform_repeatedly() {
+    let mut group = Group::new();
+    let mut sphere = Sphere::new();
+    sphere.transform = Transform::new(Matrix4x4::translation(5.0, 0.0, 0.0));
+    group.add_child(Box::new(sphere));
+    // The children's own transforms are kept, so no error builds up from undoing earlier ones
+    for step in 1..=100 {
+      let scale = 1.0 + step as f32 * 0.37;
+      group.set_transform(Transform::new(
+        Matrix4x4::rotation_y(step as f32) * Matrix4x4::scale(scale, 1.0 / scale, 3.0),
+      ));
+    }
+    group.set_transform(Transform::new(Matrix4x4::scale(2.0, 2.0, 2.0)));
+
+    let expected = Matrix4x4::scale(2.0, 2.0, 2.0) * Matrix4x4::translation(5.0, 0.0, 0.0);
+    assert!(group.children()[0].transform().matrix().approx_eq(expected));
+  }
+
+  fn nested_groups(inner_transform: Matrix4x4) -> Group {
+    let mut outer = Group::new().with_transform(Matrix4x4::rotation_y(PI / 2.0));
+    let mut inner = Group::new().with_transform(inner_transform);
+    let mut sphere = Sphere::new();
+    sphere.transform = Transform::new(Matrix4x4::translation(5.0, 0.0, 0.0));
+    inner.add_child(Box::new(sphere));
+    outer.add_child(Box::new(inner));
+    outer
+  }
+
+  #[test]
+  fn convert_point_from_world_to_object_space() {
+    let group = nested_groups(Matrix4x4::scale(2.0, 2.0, 2.0));
+    let ray = Ray::new((0.0, 0.0, -20.0), (0.0, 0.0, 1.0));
+    let intersections = group.intersect(ray);
+    let sphere = intersections[0].object;
+
+    let point = sphere.world_to_object(Point::new(-2.0, 0.0, -10.0));
+    assert!(point.approx_eq(Point::new(0.0, 0.0, -1.0)));
+  }
+
+  #[test]
+  fn convert_normal_from_object_to_world_space() {
+    let group = nested_groups(Matrix4x4::scale(1.0, 2.0, 3.0));
+    let ray = Ray::new((0.0, 0.0, -20.0), (0.0, 0.0, 1.0));
+    let intersections = group.intersect(ray);
+    let sphere = intersections[0].object;
+
+    let normal = sphere.normal_to_world(Vector::new(
+      3.0f32.sqrt() / 3.0,
+      3.0f32.sqrt() / 3.0,
+      3.0f32.sqrt() / 3.0,
+    ));
+    let expected = Vector::new(0.28571, 0.42857, -0.85714);
+    assert!((normal - expected).magnitude() < 0.0001);
+  }
+
+  #[test]
+  fn normal_on_child_object() {
+    let group = nested_groups(Matrix4x4::scale(1.0, 2.0, 3.0));
+    let ray = Ray::new((0.0, 0.0, -20.0), (0.0, 0.0, 1.0));
+    let intersections = group.intersect(ray);
+    let sphere = intersections[0].object;
+
+    let normal = sphere.normal_at(Point::new(1.7321, 1.1547, -5.5774), &intersections[0]);
+    let expected = Vector::new(0.2857, 0.42854, -0.85716);
+    assert!((normal - expected).magnitude() < 0.0001);
+  }
+
+  #[test]
+  fn group_bounds_contain_children() {
+    let mut group = Group::new();
+    let mut sphere = Sphere::new();
+    sphere.transform =
+      Transform::new(Matrix4x4::translation(2.0, 5.0, -3.0) * Matrix4x4::scale(2.0, 2.0, 2.0));
+    let mut cylinder = Cylinder::new();
+    cylinder.minimum = -2.0;
+    cylinder.maximum = 2.0;
+    cylinder.transform =
+      Transform::new(Matrix4x4::translation(-4.0, -1.0, 4.0) * Matrix4x4::scale(0.5, 1.0, 0.5));
+    group.add_child(Box::new(sphere));
+    group.add_child(Box::new(cylinder));
+
+    let bounds = group.bounds();
+    assert!(bounds.min.approx_eq(Point::new(-4.5, -3.0, -5.0)));
+    assert!(bounds.max.approx_eq(Point::new(4.0, 7.0, 4.5)));
+  }
+
+  #[test]
+  fn group_bounds_follow_its_transform() {
+    let mut group = Group::new();
+    group.add_child(Box::new(Sphere::new()));
+    group.set_transform(Transform::new(Matrix4x4::translation(10.0, 0.0, 0.0)));
+
+    let bounds = group.bounds();
+    assert!(bounds.min.approx_eq(Point::new(9.0, -1.0, -1.0)));
+    assert!(bounds.max.approx_eq(Point::new(11.0, 1.0, 1.0)));
+  }
+
+  #[test]
+  fn intersect_group_with_bvh() {
+    let mut group = Group::new();
+    for x in 0..20 {
+      let mut sphere = Sphere::new();
+      sphere.transform = Transform::new(Matrix4x4::translation(x as f32 * 3.0, 0.0, 0.0));
+      group.add_child(Box::new(sphere));
+    }
+    let ray = Ray::new((-5.0, 0.0, 0.0), (1.0, 0.0, 0.0));
+    let expected = group.intersect(ray).len();
+
+    group.build_bvh();
+    assert_eq!(group.intersect(ray).len(), expected);
+    assert_eq!(expected, 40);
+
+    let miss = Ray::new((0.0, 5.0, -5.0), (0.0, 0.0, 1.0));
+    assert!(group.intersect(miss).is_empty());
+  }
+}
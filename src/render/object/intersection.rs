@@ -6,6 +6,9 @@ use std::ops::Index;
 pub struct Intersection<'a> {
   pub t: f32,
   pub object: &'a dyn Object,
+  /// Surface parameters of the hit, used by objects such as SmoothTriangle
+  pub u: f32,
+  pub v: f32,
 }
 
 #[derive(Debug)]
@@ -14,10 +17,15 @@ pub struct IntersectionComputations<'a> {
   pub object: &'a dyn Object,
   pub position: Point,
   pub over_position: Point,
+  pub under_position: Point,
   pub eye: Vector,
   pub normal: Vector,
   pub kind: IntersectionType,
   pub reflect: Vector,
+  /// Refractive index of the material the ray is travelling from
+  pub n1: f32,
+  /// Refractive index of the material the ray is travelling into
+  pub n2: f32,
 }
 
 #[derive(Debug)]
@@ -26,13 +34,49 @@ pub enum IntersectionType {
   Outside,
 }
 
-impl Intersection<'_> {
-  pub fn prepare_computations(&self, ray: Ray) -> IntersectionComputations {
+/// Distance along the normal used to nudge positions off the surface to avoid acne
+const SURFACE_OFFSET: f32 = 0.0015;
+
+impl<'a> Intersection<'a> {
+  pub fn new(t: f32, object: &'a dyn Object) -> Self {
+    Intersection {
+      t,
+      object,
+      u: 0.0,
+      v: 0.0,
+    }
+  }
+
+  pub fn with_uv(t: f32, object: &'a dyn Object, u: f32, v: f32) -> Self {
+    Intersection { t, object, u, v }
+  }
+
+  /// Prepares the computations for this intersection, treating it as the only intersection along
+  /// the ray
+  pub fn prepare_computations(&self, ray: Ray) -> IntersectionComputations<'a> {
+    self.prepare_computations_in(ray, std::slice::from_ref(self))
+  }
+
+  /// Prepares the computations for this intersection, using the other intersections along the ray
+  /// to work out which materials the ray is refracting between
+  pub fn prepare_computations_with(
+    &self,
+    ray: Ray,
+    intersections: &IntersectionCollection<'a>,
+  ) -> IntersectionComputations<'a> {
+    self.prepare_computations_in(ray, &intersections.inner)
+  }
+
+  fn prepare_computations_in(
+    &self,
+    ray: Ray,
+    intersections: &[Intersection<'a>],
+  ) -> IntersectionComputations<'a> {
     use IntersectionType::*;
 
     let position = ray.position(self.t);
     let eye = -ray.direction;
-    let mut normal = self.object.normal_at(position);
+    let mut normal = self.object.normal_at(position, self);
     let kind;
     if normal.dot(eye) < 0.0 {
       kind = Inside;
@@ -40,20 +84,80 @@ impl Intersection<'_> {
     } else {
       kind = Outside;
     }
-    let over_position = position + normal * 0.0015;
+    let over_position = position + normal * SURFACE_OFFSET;
+    let under_position = position - normal * SURFACE_OFFSET;
     let reflect = ray.direction.reflect(normal);
+    let (n1, n2) = self.refractive_indices(intersections);
 
     IntersectionComputations {
       t: self.t,
       object: self.object,
       position,
       over_position,
+      under_position,
       eye,
       normal,
       kind,
       reflect,
+      n1,
+      n2,
     }
   }
+
+  /// Walks the intersections up to this one, tracking which objects the ray is inside of, to find
+  /// the refractive indices either side of this intersection
+  fn refractive_indices(&self, intersections: &[Intersection<'a>]) -> (f32, f32) {
+    let refractive_index = |containers: &[&dyn Object]| {
+      containers
+        .last()
+        .map_or(1.0, |o| o.material().refractive_index)
+    };
+
+    let mut containers: Vec<&dyn Object> = vec![];
+    for intersection in intersections {
+      let is_hit = intersection.same_as(self);
+      let n1 = refractive_index(&containers);
+
+      match containers
+        .iter()
+        .position(|&o| std::ptr::addr_eq(o, intersection.object))
+      {
+        Some(index) => {
+          containers.remove(index);
+        }
+        None => containers.push(intersection.object),
+      }
+
+      if is_hit {
+        return (n1, refractive_index(&containers));
+      }
+    }
+
+    (1.0, 1.0)
+  }
+
+  fn same_as(&self, rhs: &Intersection) -> bool {
+    self.t == rhs.t && std::ptr::addr_eq(self.object, rhs.object)
+  }
+}
+
+impl IntersectionComputations<'_> {
+  /// Returns the Schlick approximation of the Fresnel reflectance at this intersection
+  pub fn schlick(&self) -> f32 {
+    let mut cos = self.eye.dot(self.normal);
+
+    if self.n1 > self.n2 {
+      let n_ratio = self.n1 / self.n2;
+      let sin2_t = n_ratio * n_ratio * (1.0 - cos * cos);
+      if sin2_t > 1.0 {
+        return 1.0;
+      }
+      cos = (1.0 - sin2_t).sqrt();
+    }
+
+    let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+  }
 }
 
 // Could this be a sorted heap?
@@ -67,6 +171,12 @@ impl<'a> IntersectionCollection<'a> {
     IntersectionCollection { inner: vec![] }
   }
 
+  /// Sorts the intersections in ascending order
+  pub fn from_vec(mut vec: Vec<Intersection<'a>>) -> Self {
+    vec.sort_by(|a, b| a.t.total_cmp(&b.t));
+    IntersectionCollection { inner: vec }
+  }
+
   /// Trusts that vec is sorted in ascending order
   pub fn from_vec_unchecked(vec: Vec<Intersection<'a>>) -> Self {
     IntersectionCollection { inner: vec }
@@ -81,13 +191,8 @@ impl<'a> IntersectionCollection<'a> {
   }
 
   // this could be a binary search
-  pub fn hit(&self) -> Option<&Intersection> {
-    for intersection in &self.inner {
-      if intersection.t >= 0.0 {
-        return Some(intersection);
-      }
-    }
-    None
+  pub fn hit(&self) -> Option<&Intersection<'a>> {
+    self.inner.iter().find(|intersection| intersection.t >= 0.0)
   }
 
   /// Inserts an intersection into the collection while maintaining the sorted order
@@ -101,13 +206,36 @@ impl<'a> IntersectionCollection<'a> {
     }
   }
 
-  // TODO: this could be optimised
+  /// Merges the sorted intersections from rhs into the collection
   pub fn merge(&mut self, rhs: Self) -> &mut Self {
-    for intersection in rhs.inner {
-      self.insert(intersection)
+    if rhs.inner.is_empty() {
+      return self;
     }
+    let lhs = std::mem::take(&mut self.inner);
+    self.inner.reserve(lhs.len() + rhs.inner.len());
+
+    let (mut lhs, mut rhs) = (lhs.into_iter().peekable(), rhs.inner.into_iter().peekable());
+    while let (Some(a), Some(b)) = (lhs.peek(), rhs.peek()) {
+      let next = if a.t <= b.t { lhs.next() } else { rhs.next() };
+      self.inner.extend(next);
+    }
+    self.inner.extend(lhs);
+    self.inner.extend(rhs);
     self
   }
+
+  pub fn iter(&self) -> std::slice::Iter<'_, Intersection<'a>> {
+    self.inner.iter()
+  }
+}
+
+impl<'a> IntoIterator for IntersectionCollection<'a> {
+  type Item = Intersection<'a>;
+  type IntoIter = std::vec::IntoIter<Intersection<'a>>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.inner.into_iter()
+  }
 }
 
 impl<'a> Index<usize> for IntersectionCollection<'a> {
@@ -122,17 +250,18 @@ impl<'a> Index<usize> for IntersectionCollection<'a> {
 mod tests {
   use super::*;
 
+  fn glass_sphere() -> Sphere {
+    let mut sphere = Sphere::new();
+    sphere.material.transparency = 1.0;
+    sphere.material.refractive_index = 1.5;
+    sphere
+  }
+
   #[test]
   fn hit_all_positive_ts() {
     let sphere = Sphere::new();
-    let intersection_a = Intersection {
-      t: 1.0,
-      object: &sphere,
-    };
-    let intersection_b = Intersection {
-      t: 2.0,
-      object: &sphere,
-    };
+    let intersection_a = Intersection::new(1.0, &sphere);
+    let intersection_b = Intersection::new(2.0, &sphere);
 
     let intersections =
       IntersectionCollection::from_vec_unchecked(vec![intersection_a, intersection_b]);
@@ -144,14 +273,8 @@ mod tests {
   #[test]
   fn hit_some_negative_ts() {
     let sphere = Sphere::new();
-    let intersection_a = Intersection {
-      t: -1.0,
-      object: &sphere,
-    };
-    let intersection_b = Intersection {
-      t: 1.0,
-      object: &sphere,
-    };
+    let intersection_a = Intersection::new(-1.0, &sphere);
+    let intersection_b = Intersection::new(1.0, &sphere);
 
     let intersections =
       IntersectionCollection::from_vec_unchecked(vec![intersection_a, intersection_b]);
@@ -163,14 +286,8 @@ mod tests {
   #[test]
   fn hit_all_negative_ts() {
     let sphere = Sphere::new();
-    let intersection_a = Intersection {
-      t: -2.0,
-      object: &sphere,
-    };
-    let intersection_b = Intersection {
-      t: -1.0,
-      object: &sphere,
-    };
+    let intersection_a = Intersection::new(-2.0, &sphere);
+    let intersection_b = Intersection::new(-1.0, &sphere);
 
     let intersections =
       IntersectionCollection::from_vec_unchecked(vec![intersection_a, intersection_b]);
@@ -183,31 +300,20 @@ mod tests {
   fn hit_is_lowest_nonnegative() {
     let sphere = Sphere::new();
 
-    let intersection_a = Intersection {
-      t: 5.0,
-      object: &sphere,
-    };
-    let intersection_b = Intersection {
-      t: 7.0,
-      object: &sphere,
-    };
-    let intersection_c = Intersection {
-      t: -3.0,
-      object: &sphere,
-    };
-    let _intersection_d = Intersection {
-      t: 2.0,
-      object: &sphere,
-    };
+    let intersection_a = Intersection::new(5.0, &sphere);
+    let intersection_b = Intersection::new(7.0, &sphere);
+    let intersection_c = Intersection::new(-3.0, &sphere);
+    let _intersection_d = Intersection::new(2.0, &sphere);
 
     let mut intersections = IntersectionCollection::new();
     [
       intersection_a,
       intersection_b,
       intersection_c,
-      _intersection_d.clone(),
+      _intersection_d,
     ]
-    .map(|i| intersections.insert(i));
+    .into_iter()
+    .for_each(|i| intersections.insert(i));
 
     let _hit = intersections.hit().unwrap();
     assert!(matches!(_hit, _intersection_d));
@@ -217,10 +323,7 @@ mod tests {
   fn external_intersection() {
     let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
     let shape = Sphere::new();
-    let intersection = Intersection {
-      t: 4.0,
-      object: &shape,
-    };
+    let intersection = Intersection::new(4.0, &shape);
     let _computations = intersection.prepare_computations(ray);
     assert!(matches!(_computations.kind, IntersectionType::Outside));
   }
@@ -229,10 +332,7 @@ mod tests {
   fn internal_intersection() {
     let ray = Ray::new((0.0, 0.0, 0.0), (0.0, 0.0, 1.0));
     let shape = Sphere::new();
-    let intersection = Intersection {
-      t: 1.0,
-      object: &shape,
-    };
+    let intersection = Intersection::new(1.0, &shape);
     let _computations = intersection.prepare_computations(ray);
     assert!(matches!(_computations.kind, IntersectionType::Inside));
     assert!(_computations.eye.approx_eq(Vector::from((0.0, 0.0, -1.0))));
@@ -248,11 +348,8 @@ mod tests {
   fn hit_over_position_property() {
     let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
     let mut sphere = Sphere::new();
-    sphere.transform = Matrix4x4::translation(0.0, 0.0, 1.0);
-    let intersection = Intersection {
-      t: 5.0,
-      object: &sphere,
-    };
+    sphere.transform = Transform::new(Matrix4x4::translation(0.0, 0.0, 1.0));
+    let intersection = Intersection::new(5.0, &sphere);
     let computations = intersection.prepare_computations(ray);
     assert!(computations.over_position.z < -EPSILON / 2.0);
     assert!(computations.position.z > computations.over_position.z);
@@ -265,13 +362,114 @@ mod tests {
       (0.0, 1.0, -1.0),
       (0.0, -1.0 / 2.0f32.sqrt(), 1.0 / 2.0f32.sqrt()),
     );
-    let intersection = Intersection {
-      t: 2.0f32.sqrt(),
-      object: &shape,
-    };
+    let intersection = Intersection::new(2.0f32.sqrt(), &shape);
     let reflect = intersection.prepare_computations(ray).reflect;
 
     let expected = Vector::new(0.0, 1.0 / 2.0f32.sqrt(), 1.0 / 2.0f32.sqrt());
     assert!(reflect.approx_eq(expected));
   }
+
+  #[test]
+  fn n1_and_n2_at_various_intersections() {
+    let mut a = glass_sphere();
+    a.transform = Transform::new(Matrix4x4::scale(2.0, 2.0, 2.0));
+    let mut b = glass_sphere();
+    b.transform = Transform::new(Matrix4x4::translation(0.0, 0.0, -0.25));
+    b.material.refractive_index = 2.0;
+    let mut c = glass_sphere();
+    c.transform = Transform::new(Matrix4x4::translation(0.0, 0.0, 0.25));
+    c.material.refractive_index = 2.5;
+
+    let ray = Ray::new((0.0, 0.0, -4.0), (0.0, 0.0, 1.0));
+    let intersections = IntersectionCollection::from_vec_unchecked(vec![
+      Intersection::new(2.0, &a),
+      Intersection::new(2.75, &b),
+      Intersection::new(3.25, &c),
+      Intersection::new(4.75, &b),
+      Intersection::new(5.25, &c),
+      Intersection::new(6.0, &a),
+    ]);
+
+    let expected = [
+      (1.0, 1.5),
+      (1.5, 2.0),
+      (2.0, 2.5),
+      (2.5, 2.5),
+      (2.5, 1.5),
+      (1.5, 1.0),
+    ];
+    for (index, (n1, n2)) in expected.into_iter().enumerate() {
+      let computations = intersections[index].prepare_computations_with(ray, &intersections);
+      assert!(computations.n1.approx_eq(n1));
+      assert!(computations.n2.approx_eq(n2));
+    }
+  }
+
+  #[test]
+  fn under_position_is_below_the_surface() {
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let mut sphere = glass_sphere();
+    sphere.transform = Transform::new(Matrix4x4::translation(0.0, 0.0, 1.0));
+    let intersection = Intersection::new(5.0, &sphere);
+    let intersections = IntersectionCollection::from_vec_unchecked(vec![intersection]);
+    let computations = intersection.prepare_computations_with(ray, &intersections);
+
+    assert!(computations.under_position.z > EPSILON / 2.0);
+    assert!(computations.position.z < computations.under_position.z);
+  }
+
+  #[test]
+  fn schlick_under_total_internal_reflection() {
+    let sphere = glass_sphere();
+    let ray = Ray::new((0.0, 0.0, 1.0 / 2.0f32.sqrt()), (0.0, 1.0, 0.0));
+    let intersections = IntersectionCollection::from_vec_unchecked(vec![
+      Intersection::new(-1.0 / 2.0f32.sqrt(), &sphere),
+      Intersection::new(1.0 / 2.0f32.sqrt(), &sphere),
+    ]);
+    let computations = intersections[1].prepare_computations_with(ray, &intersections);
+
+    assert!(computations.schlick().approx_eq(1.0));
+  }
+
+  #[test]
+  fn schlick_with_perpendicular_viewing_angle() {
+    let sphere = glass_sphere();
+    let ray = Ray::new(Point::ORIGIN, (0.0, 1.0, 0.0));
+    let intersections = IntersectionCollection::from_vec_unchecked(vec![
+      Intersection::new(-1.0, &sphere),
+      Intersection::new(1.0, &sphere),
+    ]);
+    let computations = intersections[1].prepare_computations_with(ray, &intersections);
+
+    assert!(computations.schlick().approx_eq(0.04));
+  }
+
+  #[test]
+  fn schlick_with_small_angle_and_n2_greater_than_n1() {
+    let sphere = glass_sphere();
+    let ray = Ray::new((0.0, 0.99, -2.0), (0.0, 0.0, 1.0));
+    let intersections =
+      IntersectionCollection::from_vec_unchecked(vec![Intersection::new(1.8589, &sphere)]);
+    let computations = intersections[0].prepare_computations_with(ray, &intersections);
+
+    assert!((computations.schlick() - 0.48873).abs() < 0.0001);
+  }
+
+  #[test]
+  fn merge_sorted_collections() {
+    let sphere = Sphere::new();
+    let mut lhs = IntersectionCollection::from_vec(vec![
+      Intersection::new(3.0, &sphere),
+      Intersection::new(-1.0, &sphere),
+      Intersection::new(5.0, &sphere),
+    ]);
+    let rhs = IntersectionCollection::from_vec(vec![
+      Intersection::new(4.0, &sphere),
+      Intersection::new(0.5, &sphere),
+    ]);
+    lhs.merge(rhs);
+
+    let ts = lhs.iter().map(|i| i.t).collect::<Vec<_>>();
+    assert_eq!(ts, vec![-1.0, 0.5, 3.0, 4.0, 5.0]);
+  }
 }
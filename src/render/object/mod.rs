@@ -1,32 +1,101 @@
+pub mod bounds;
+pub mod bvh;
+pub mod cone;
+pub mod csg;
+pub mod cube;
+pub mod cylinder;
+pub mod group;
 pub mod intersection;
+pub mod obj_file;
 pub mod plane;
+pub mod smooth_triangle;
 pub mod sphere;
+pub mod triangle;
+pub use bounds::*;
+pub use bvh::*;
+pub use cone::*;
+pub use csg::*;
+pub use cube::*;
+pub use cylinder::*;
+pub use group::*;
 pub use intersection::*;
+pub use obj_file::*;
 pub use plane::*;
+pub use smooth_triangle::*;
 pub use sphere::*;
+pub use triangle::*;
 
 use crate::*;
+#[cfg(feature = "heatmap")]
+use std::cell::Cell;
 use std::fmt::Debug;
 
+#[cfg(feature = "heatmap")]
+thread_local! {
+  static INTERSECTION_TESTS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Number of ray-object and ray-bounding box tests performed so far on the current thread, for
+/// diagnostics such as [`HeatmapView`]
+#[cfg(feature = "heatmap")]
+pub fn intersection_tests() -> usize {
+  INTERSECTION_TESTS.with(Cell::get)
+}
+
+/// Counts an intersection test when the `heatmap` feature is enabled, and does nothing otherwise
+#[inline(always)]
+pub(crate) fn count_intersection_test() {
+  #[cfg(feature = "heatmap")]
+  INTERSECTION_TESTS.with(|tests| tests.set(tests.get() + 1));
+}
+
 pub trait Object: Debug + Send + Sync {
-  fn intersect(&self, ray: Ray) -> IntersectionCollection {
-    self.local_intersect(self.transform().inverse().unwrap() * ray)
+  fn intersect(&self, ray: Ray) -> IntersectionCollection<'_> {
+    count_intersection_test();
+    self.local_intersect(self.transform().inverse() * ray)
+  }
+
+  fn normal_at(&self, point: Point, hit: &Intersection) -> Vector {
+    let object_normal = self.local_normal_at(self.world_to_object(point), hit);
+    self.normal_to_world(object_normal)
   }
 
-  fn normal_at(&self, point: Point) -> Vector {
-    let inverse_transform = &self.transform().inverse().unwrap();
-    let object_normal = self.local_normal_at(inverse_transform * point);
-    let world_normal = inverse_transform
-      .transpose()
-      .mul_vec_unchecked(object_normal);
-    world_normal.normalise()
+  /// Converts a point in world space to the object's local space
+  fn world_to_object(&self, point: Point) -> Point {
+    self.transform().inverse() * point
   }
 
-  fn local_intersect(&self, ray: Ray) -> IntersectionCollection;
+  /// Converts a normal in the object's local space to world space
+  fn normal_to_world(&self, normal: Vector) -> Vector {
+    self
+      .transform()
+      .inverse_transpose()
+      .mul_vec_unchecked(normal)
+      .normalise()
+  }
+
+  fn local_intersect(&self, ray: Ray) -> IntersectionCollection<'_>;
+
+  fn local_normal_at(&self, point: Point, hit: &Intersection) -> Vector;
+
+  /// Returns the bounding box of the object in world space
+  fn bounds(&self) -> Bounds {
+    self.local_bounds().transform(self.transform().matrix())
+  }
 
-  fn local_normal_at(&self, point: Point) -> Vector;
+  /// Returns the bounding box of the object in its local space
+  fn local_bounds(&self) -> Bounds;
 
   fn material(&self) -> &Material;
 
-  fn transform(&self) -> &Matrix4x4;
+  /// The object's transform from local space to world space, including the transforms of any
+  /// groups the object is part of
+  fn transform(&self) -> &Transform;
+
+  fn set_transform(&mut self, transform: Transform);
+
+  /// Returns whether the given object is this object, or is contained within it
+  fn includes(&self, object: &dyn Object) -> bool {
+    std::ptr::addr_eq(self, object)
+  }
 }
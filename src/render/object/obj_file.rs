@@ -0,0 +1,489 @@
+use crate::*;
+use std::{fmt, path::Path};
+
+/// A triangle read from an OBJ file, with vertex normals if the face specified them
+#[derive(Debug, Clone, Copy)]
+pub struct ObjTriangle {
+  pub points: (Point, Point, Point),
+  pub normals: Option<(Vector, Vector, Vector)>,
+}
+
+/// The geometry read from a Wavefront OBJ file
+#[derive(Debug, Default)]
+pub struct ObjFile {
+  pub vertices: Vec<Point>,
+  pub normals: Vec<Vector>,
+  pub texture_coordinates: Vec<(f32, f32)>,
+  /// Number of lines that were not recognised as statements and so were skipped
+  pub ignored_lines: usize,
+  /// Triangles that appear before any `g` statement
+  pub default_group: Vec<ObjTriangle>,
+  /// Triangles under each named `g` statement, in the order the groups first appear
+  pub groups: Vec<(String, Vec<ObjTriangle>)>,
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+  Io(std::io::Error),
+  /// A malformed line, with its 1-based line number
+  Parse {
+    line: usize,
+    message: String,
+  },
+}
+
+impl fmt::Display for ObjError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ObjError::Io(error) => write!(f, "failed to read OBJ file: {error}"),
+      ObjError::Parse { line, message } => write!(f, "line {line}: {message}"),
+    }
+  }
+}
+
+impl std::error::Error for ObjError {}
+
+impl From<std::io::Error> for ObjError {
+  fn from(error: std::io::Error) -> Self {
+    ObjError::Io(error)
+  }
+}
+
+impl ObjFile {
+  pub fn open(path: impl AsRef<Path>) -> Result<Self, ObjError> {
+    Self::parse(&std::fs::read_to_string(path)?)
+  }
+
+  pub fn parse(input: &str) -> Result<Self, ObjError> {
+    let mut obj = ObjFile::default();
+    // None while adding to the default group, otherwise an index into `groups`
+    let mut current_group: Option<usize> = None;
+
+    for (index, line) in input.lines().enumerate() {
+      let error = |message: String| ObjError::Parse {
+        line: index + 1,
+        message,
+      };
+      let mut tokens = line.split_whitespace();
+
+      match tokens.next() {
+        Some("v") => {
+          let [x, y, z] = parse_floats(tokens).map_err(error)?;
+          obj.vertices.push(Point::new(x, y, z));
+        }
+        Some("vn") => {
+          let [x, y, z] = parse_floats(tokens).map_err(error)?;
+          obj.normals.push(Vector::new(x, y, z));
+        }
+        Some("vt") => {
+          // Only u is required: v defaults to 0 and w is ignored
+          let values = parse_numbers(tokens).map_err(error)?;
+          let (u, v) = match values[..] {
+            [u] => (u, 0.0),
+            [u, v] | [u, v, _] => (u, v),
+            _ => {
+              return Err(error(format!(
+                "expected 1 to 3 numbers, found {}",
+                values.len()
+              )))
+            }
+          };
+          obj.texture_coordinates.push((u, v));
+        }
+        Some("f") => {
+          let triangles = obj.parse_face(tokens).map_err(error)?;
+          match current_group {
+            None => obj.default_group.extend(triangles),
+            Some(group) => obj.groups[group].1.extend(triangles),
+          }
+        }
+        Some("g") => {
+          let name = tokens.collect::<Vec<_>>().join(" ");
+          // A group statement without a name returns to the default group
+          if name.is_empty() {
+            current_group = None;
+            continue;
+          }
+          current_group = Some(
+            match obj
+              .groups
+              .iter()
+              .position(|(existing, _)| *existing == name)
+            {
+              Some(group) => group,
+              None => {
+                obj.groups.push((name, vec![]));
+                obj.groups.len() - 1
+              }
+            },
+          );
+        }
+        None => {}
+        Some(comment) if comment.starts_with('#') => {}
+        Some(_) => obj.ignored_lines += 1,
+      }
+    }
+
+    Ok(obj)
+  }
+
+  /// Returns the named group's triangles
+  pub fn group(&self, name: &str) -> Option<&[ObjTriangle]> {
+    self
+      .groups
+      .iter()
+      .find(|(group, _)| group == name)
+      .map(|(_, triangles)| &triangles[..])
+  }
+
+  /// Converts the triangles into a Group, with each named group as a child Group.
+  ///
+  /// Faces with vertex normals become SmoothTriangles, and all other faces become Triangles. Each
+  /// group has a bounding volume hierarchy built over its children.
+  pub fn into_group(self, material: &Material) -> Group {
+    let to_group = |triangles: Vec<ObjTriangle>| {
+      let mut group = Group::new();
+      for triangle in triangles {
+        group.add_child(triangle.into_object(material));
+      }
+      group.build_bvh();
+      group
+    };
+
+    let mut group = to_group(self.default_group);
+    for (_, triangles) in self.groups {
+      group.add_child(Box::new(to_group(triangles)));
+    }
+    group.build_bvh();
+    group
+  }
+
+  /// Splits a polygonal face into triangles that fan out from its first vertex
+  fn parse_face<'a>(
+    &self,
+    tokens: impl Iterator<Item = &'a str>,
+  ) -> Result<Vec<ObjTriangle>, String> {
+    let vertices = tokens
+      .map(|token| self.parse_face_vertex(token))
+      .collect::<Result<Vec<_>, _>>()?;
+    if vertices.len() < 3 {
+      return Err(format!(
+        "face has {} vertices, expected at least 3",
+        vertices.len()
+      ));
+    }
+
+    let (first, first_normal) = vertices[0];
+    Ok(
+      vertices[1..]
+        .windows(2)
+        .map(|pair| {
+          let [(second, second_normal), (third, third_normal)] = [pair[0], pair[1]];
+          ObjTriangle {
+            points: (first, second, third),
+            normals: first_normal
+              .zip(second_normal)
+              .zip(third_normal)
+              .map(|((a, b), c)| (a, b, c)),
+          }
+        })
+        .collect(),
+    )
+  }
+
+  /// Parses a face vertex in any of the forms `v`, `v/vt`, `v//vn` or `v/vt/vn`
+  fn parse_face_vertex(&self, token: &str) -> Result<(Point, Option<Vector>), String> {
+    let mut indices = token.split('/');
+    let vertex = indices.next().unwrap_or_default();
+    let texture_coordinate = indices.next().filter(|index| !index.is_empty());
+    let normal = indices.next().filter(|normal| !normal.is_empty());
+    if indices.next().is_some() {
+      return Err(format!("malformed face vertex {token:?}"));
+    }
+
+    let vertex = self.vertices[resolve_index(vertex, self.vertices.len())?];
+    // Texture coordinates aren't used yet, but must still refer to a record
+    if let Some(texture_coordinate) = texture_coordinate {
+      resolve_index(texture_coordinate, self.texture_coordinates.len())?;
+    }
+    let normal = match normal {
+      Some(normal) => Some(self.normals[resolve_index(normal, self.normals.len())?]),
+      None => None,
+    };
+    Ok((vertex, normal))
+  }
+}
+
+impl ObjTriangle {
+  fn into_object(self, material: &Material) -> Box<dyn Object> {
+    match self.normals {
+      Some(normals) => {
+        let mut triangle = SmoothTriangle::new(self.points, normals);
+        triangle.material = material.clone();
+        Box::new(triangle)
+      }
+      None => {
+        let (p1, p2, p3) = self.points;
+        let mut triangle = Triangle::new(p1, p2, p3);
+        triangle.material = material.clone();
+        Box::new(triangle)
+      }
+    }
+  }
+}
+
+/// Parses the first N tokens as numbers, ignoring any optional components after them
+fn parse_floats<'a, const N: usize>(
+  tokens: impl Iterator<Item = &'a str>,
+) -> Result<[f32; N], String> {
+  let values = parse_numbers(tokens)?;
+  if values.len() < N {
+    return Err(format!("expected {N} numbers, found {}", values.len()));
+  }
+  Ok(values[..N].try_into().unwrap())
+}
+
+fn parse_numbers<'a>(tokens: impl Iterator<Item = &'a str>) -> Result<Vec<f32>, String> {
+  tokens
+    .map(|token| {
+      token
+        .parse::<f32>()
+        .map_err(|_| format!("invalid number {token:?}"))
+    })
+    .collect()
+}
+
+/// Converts a 1-based OBJ index, or a negative index counting back from the most recent element,
+/// into an index into a list of `len` elements
+fn resolve_index(token: &str, len: usize) -> Result<usize, String> {
+  let index = token
+    .parse::<isize>()
+    .map_err(|_| format!("invalid index {token:?}"))?;
+
+  let resolved = match index {
+    1.. => index - 1,
+    ..=-1 => len as isize + index,
+    0 => return Err("indices start at 1".into()),
+  };
+  if resolved < 0 || resolved as usize >= len {
+    return Err(format!("index {index} is out of range"));
+  }
+  Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ignore_unrecognised_lines() {
+    let gibberish = "There was a young lady named Bright
+who traveled much faster than light.
+She set out one day
+in a relative way,
+and came back the previous night.";
+    let obj = ObjFile::parse(gibberish).unwrap();
+
+    assert_eq!(obj.ignored_lines, 5);
+  }
+
+  #[test]
+  fn vertex_records() {
+    let file = "v -1 1 0
+v -1.0000 0.5000 0.0000
+v 1 0 0
+v 1 1 0";
+    let obj = ObjFile::parse(file).unwrap();
+
+    assert!(obj.vertices[0].approx_eq(Point::new(-1.0, 1.0, 0.0)));
+    assert!(obj.vertices[1].approx_eq(Point::new(-1.0, 0.5, 0.0)));
+    assert!(obj.vertices[2].approx_eq(Point::new(1.0, 0.0, 0.0)));
+    assert!(obj.vertices[3].approx_eq(Point::new(1.0, 1.0, 0.0)));
+  }
+
+  #[test]
+  fn triangle_faces() {
+    let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 3
+f 1 3 4";
+    let obj = ObjFile::parse(file).unwrap();
+    let (t1, t2) = (obj.default_group[0], obj.default_group[1]);
+
+    assert!(t1.points.0.approx_eq(obj.vertices[0]));
+    assert!(t1.points.1.approx_eq(obj.vertices[1]));
+    assert!(t1.points.2.approx_eq(obj.vertices[2]));
+    assert!(t2.points.0.approx_eq(obj.vertices[0]));
+    assert!(t2.points.1.approx_eq(obj.vertices[2]));
+    assert!(t2.points.2.approx_eq(obj.vertices[3]));
+  }
+
+  #[test]
+  fn triangulate_polygons() {
+    let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+v 0 2 0
+
+f 1 2 3 4 5";
+    let obj = ObjFile::parse(file).unwrap();
+
+    assert_eq!(obj.default_group.len(), 3);
+    for (triangle, (b, c)) in obj.default_group.iter().zip([(1, 2), (2, 3), (3, 4)]) {
+      assert!(triangle.points.0.approx_eq(obj.vertices[0]));
+      assert!(triangle.points.1.approx_eq(obj.vertices[b]));
+      assert!(triangle.points.2.approx_eq(obj.vertices[c]));
+    }
+  }
+
+  #[test]
+  fn triangles_in_groups() {
+    let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+    let obj = ObjFile::parse(file).unwrap();
+    let first = obj.group("FirstGroup").unwrap();
+    let second = obj.group("SecondGroup").unwrap();
+
+    assert!(obj.default_group.is_empty());
+    assert_eq!(first.len(), 1);
+    assert!(first[0].points.2.approx_eq(obj.vertices[2]));
+    assert_eq!(second.len(), 1);
+    assert!(second[0].points.2.approx_eq(obj.vertices[3]));
+  }
+
+  #[test]
+  fn unnamed_group_returns_to_default_group() {
+    let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+g FirstGroup
+f 1 2 3
+g
+f 1 3 4";
+    let obj = ObjFile::parse(file).unwrap();
+
+    assert_eq!(obj.group("FirstGroup").unwrap().len(), 1);
+    assert_eq!(obj.default_group.len(), 1);
+    assert!(obj.default_group[0].points.2.approx_eq(obj.vertices[3]));
+  }
+
+  #[test]
+  fn convert_obj_file_to_group() {
+    let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 4
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+    let group = ObjFile::parse(file)
+      .unwrap()
+      .into_group(&Material::default());
+
+    assert_eq!(group.children().len(), 3);
+    let ray = Ray::new((0.5, 0.75, -1.0), (0.0, 0.0, 1.0));
+    assert_eq!(group.intersect(ray).len(), 2);
+  }
+
+  #[test]
+  fn vertex_normal_records() {
+    let file = "vn 0 0 1
+vn 0.707 0 -0.707
+vn 1 2 3";
+    let obj = ObjFile::parse(file).unwrap();
+
+    assert!(obj.normals[0].approx_eq(Vector::new(0.0, 0.0, 1.0)));
+    assert!(obj.normals[1].approx_eq(Vector::new(0.707, 0.0, -0.707)));
+    assert!(obj.normals[2].approx_eq(Vector::new(1.0, 2.0, 3.0)));
+  }
+
+  #[test]
+  fn faces_with_normals() {
+    let file = "v 0 1 0
+v -1 0 0
+v 1 0 0
+
+vn -1 0 0
+vn 1 0 0
+vn 0 1 0
+
+vt 0 0
+vt 1 0
+vt 0 1
+
+f 1//3 2//1 3//2
+f 1/1/3 2/3/1 3/2/2";
+    let obj = ObjFile::parse(file).unwrap();
+
+    for triangle in &obj.default_group {
+      let (n1, n2, n3) = triangle.normals.unwrap();
+      assert!(triangle.points.0.approx_eq(obj.vertices[0]));
+      assert!(n1.approx_eq(obj.normals[2]));
+      assert!(n2.approx_eq(obj.normals[0]));
+      assert!(n3.approx_eq(obj.normals[1]));
+    }
+  }
+
+  #[test]
+  fn texture_coordinates_with_optional_components() {
+    let file = "vt 0.5
+vt 0.5 0.5 0
+vt 0.25 0.75";
+    let obj = ObjFile::parse(file).unwrap();
+
+    assert_eq!(
+      obj.texture_coordinates,
+      vec![(0.5, 0.0), (0.5, 0.5), (0.25, 0.75)]
+    );
+  }
+
+  #[test]
+  fn negative_indices_count_back_from_the_end() {
+    let file = "v -1 1 0
+v -1 0 0
+v 1 0 0
+f -3 -2 -1";
+    let obj = ObjFile::parse(file).unwrap();
+
+    assert!(obj.default_group[0].points.0.approx_eq(obj.vertices[0]));
+    assert!(obj.default_group[0].points.2.approx_eq(obj.vertices[2]));
+  }
+
+  #[test]
+  fn malformed_lines_report_line_number() {
+    let cases = [
+      ("v 1 2 3\nv 1 two 3", 2),
+      ("v 1 2", 1),
+      ("v 1 2 3\nv 1 2 3\nf 1 2", 3),
+      ("v 1 2 3\nv 1 2 3\nv 1 2 3\n\nf 1 2 4", 5),
+      ("v 1 2 3\nv 1 2 3\nv 1 2 3\nf 1 2 0", 4),
+      ("v 1 2 3\nv 1 2 3\nv 1 2 3\nf 1//1 2 3", 4),
+      ("v 1 2 3\nv 1 2 3\nv 1 2 3\nvt 0 0\nf 1/1 2/2 3/1", 5),
+      ("vt 0 0\nvt", 2),
+      ("vt 0 0 0 0", 1),
+    ];
+
+    for (file, expected) in cases {
+      match ObjFile::parse(file) {
+        Err(ObjError::Parse { line, .. }) => assert_eq!(line, expected),
+        result => panic!("expected a parse error, got {result:?}"),
+      }
+    }
+  }
+}
@@ -4,40 +4,51 @@ use crate::*;
 #[derive(Debug)]
 pub struct Plane {
   pub material: Material,
-  pub transform: Matrix4x4,
+  pub transform: Transform,
 }
 
 impl Plane {
   pub fn new() -> Self {
     Plane {
       material: Material::default(),
-      transform: Matrix4x4::IDENTITY,
+      transform: Transform::IDENTITY,
     }
   }
 }
 
 impl Object for Plane {
-  fn local_intersect(&self, ray: Ray) -> IntersectionCollection {
+  fn local_intersect(&self, ray: Ray) -> IntersectionCollection<'_> {
     if !ray.direction.y.approx_eq(0.0) {
-      return IntersectionCollection::from_vec_unchecked(vec![Intersection {
-        t: -ray.origin.y / ray.direction.y,
-        object: self,
-      }]);
+      return IntersectionCollection::from_vec_unchecked(vec![Intersection::new(
+        -ray.origin.y / ray.direction.y,
+        self,
+      )]);
     }
     IntersectionCollection::new()
   }
 
-  fn local_normal_at(&self, _: Point) -> Vector {
+  fn local_normal_at(&self, _: Point, _: &Intersection) -> Vector {
     Vector::new(0.0, 1.0, 0.0)
   }
 
+  fn local_bounds(&self) -> Bounds {
+    Bounds::new(
+      (f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY),
+      (f32::INFINITY, 0.0, f32::INFINITY),
+    )
+  }
+
   fn material(&self) -> &Material {
     &self.material
   }
 
-  fn transform(&self) -> &Matrix4x4 {
+  fn transform(&self) -> &Transform {
     &self.transform
   }
+
+  fn set_transform(&mut self, transform: Transform) {
+    self.transform = transform;
+  }
 }
 
 #[cfg(test)]
@@ -47,9 +58,15 @@ mod tests {
   #[test]
   fn plane_normal_is_constant_everywhere() {
     let plane = Plane::new();
-    let normal_1 = plane.local_normal_at(Point::ORIGIN);
-    let normal_2 = plane.local_normal_at(Point::new(10.0, 0.0, -10.0));
-    let normal_3 = plane.local_normal_at(Point::new(-5.0, 0.0, 150.0));
+    let normal_1 = plane.local_normal_at(Point::ORIGIN, &Intersection::new(0.0, &plane));
+    let normal_2 = plane.local_normal_at(
+      Point::new(10.0, 0.0, -10.0),
+      &Intersection::new(0.0, &plane),
+    );
+    let normal_3 = plane.local_normal_at(
+      Point::new(-5.0, 0.0, 150.0),
+      &Intersection::new(0.0, &plane),
+    );
 
     let expected = Vector::new(0.0, 1.0, 0.0);
     assert!(normal_1.approx_eq(expected));
@@ -92,4 +109,14 @@ mod tests {
     assert_eq!(intersections.len(), 1);
     assert!(intersections[0].t.approx_eq(1.0));
   }
+
+  #[test]
+  fn plane_bounds_are_infinite_in_x_and_z() {
+    let bounds = Plane::new().local_bounds();
+
+    assert_eq!(bounds.min.x, f32::NEG_INFINITY);
+    assert!(bounds.min.y.approx_eq(0.0));
+    assert_eq!(bounds.max.z, f32::INFINITY);
+    assert!(bounds.max.y.approx_eq(0.0));
+  }
 }
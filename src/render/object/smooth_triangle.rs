@@ -0,0 +1,127 @@
+use super::triangle::intersect_triangle;
+use crate::*;
+
+/// A triangle whose normal is interpolated between normals given at each of its points
+#[derive(Debug)]
+pub struct SmoothTriangle {
+  pub material: Material,
+  pub transform: Transform,
+  p1: Point,
+  p2: Point,
+  p3: Point,
+  n1: Vector,
+  n2: Vector,
+  n3: Vector,
+  e1: Vector,
+  e2: Vector,
+}
+
+impl SmoothTriangle {
+  pub fn new(
+    (p1, p2, p3): (impl Into<Point>, impl Into<Point>, impl Into<Point>),
+    (n1, n2, n3): (impl Into<Vector>, impl Into<Vector>, impl Into<Vector>),
+  ) -> Self {
+    let (p1, p2, p3) = (p1.into(), p2.into(), p3.into());
+
+    SmoothTriangle {
+      material: Material::default(),
+      transform: Transform::IDENTITY,
+      p1,
+      p2,
+      p3,
+      n1: n1.into(),
+      n2: n2.into(),
+      n3: n3.into(),
+      e1: p2 - p1,
+      e2: p3 - p1,
+    }
+  }
+
+  pub fn points(&self) -> (Point, Point, Point) {
+    (self.p1, self.p2, self.p3)
+  }
+
+  pub fn normals(&self) -> (Vector, Vector, Vector) {
+    (self.n1, self.n2, self.n3)
+  }
+}
+
+impl Object for SmoothTriangle {
+  fn local_intersect(&self, ray: Ray) -> IntersectionCollection<'_> {
+    match intersect_triangle(self.p1, self.e1, self.e2, ray) {
+      Some((t, u, v)) => {
+        IntersectionCollection::from_vec_unchecked(vec![Intersection::with_uv(t, self, u, v)])
+      }
+      None => IntersectionCollection::new(),
+    }
+  }
+
+  /// Interpolates the vertex normals using the barycentric coordinates of the hit
+  fn local_normal_at(&self, _: Point, hit: &Intersection) -> Vector {
+    self.n2 * hit.u + self.n3 * hit.v + self.n1 * (1.0 - hit.u - hit.v)
+  }
+
+  fn local_bounds(&self) -> Bounds {
+    let mut bounds = Bounds::EMPTY;
+    for point in [self.p1, self.p2, self.p3] {
+      bounds.add_point(point);
+    }
+    bounds
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Transform) {
+    self.transform = transform;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn test_smooth_triangle() -> SmoothTriangle {
+    SmoothTriangle::new(
+      ((0.0, 1.0, 0.0), (-1.0, 0.0, 0.0), (1.0, 0.0, 0.0)),
+      ((0.0, 1.0, 0.0), (-1.0, 0.0, 0.0), (1.0, 0.0, 0.0)),
+    )
+  }
+
+  #[test]
+  fn intersection_stores_u_and_v() {
+    let triangle = test_smooth_triangle();
+    let ray = Ray::new((-0.2, 0.3, -2.0), (0.0, 0.0, 1.0));
+    let intersections = triangle.local_intersect(ray);
+
+    assert_eq!(intersections.len(), 1);
+    assert!(intersections[0].u.approx_eq(0.45));
+    assert!(intersections[0].v.approx_eq(0.25));
+  }
+
+  #[test]
+  fn normal_is_interpolated_with_u_and_v() {
+    let triangle = test_smooth_triangle();
+    let hit = Intersection::with_uv(1.0, &triangle, 0.45, 0.25);
+    let normal = triangle.normal_at(Point::ORIGIN, &hit);
+
+    let expected = Vector::new(-0.5547, 0.83205, 0.0);
+    assert!((normal - expected).magnitude() < 0.0001);
+  }
+
+  #[test]
+  fn prepare_computations_with_smooth_triangle() {
+    let triangle = test_smooth_triangle();
+    let hit = Intersection::with_uv(1.0, &triangle, 0.45, 0.25);
+    let ray = Ray::new((-0.2, 0.3, -2.0), (0.0, 0.0, 1.0));
+    let computations = hit.prepare_computations(ray);
+
+    let expected = Vector::new(-0.5547, 0.83205, 0.0);
+    assert!((computations.normal - expected).magnitude() < 0.0001);
+  }
+}
@@ -2,14 +2,14 @@ use crate::*;
 
 #[derive(Debug)]
 pub struct Sphere {
-  pub transform: Matrix4x4,
+  pub transform: Transform,
   pub material: Material,
 }
 
 impl Sphere {
   pub fn new() -> Self {
     Sphere {
-      transform: Matrix4x4::IDENTITY,
+      transform: Transform::IDENTITY,
       material: Material::default(),
     }
   }
@@ -17,7 +17,7 @@ impl Sphere {
 
 impl Object for Sphere {
   /// Returns the t values of the ray where it instersects with the Sphere
-  fn local_intersect(&self, ray: Ray) -> IntersectionCollection {
+  fn local_intersect(&self, ray: Ray) -> IntersectionCollection<'_> {
     let sphere_to_ray = ray.origin - Point::ORIGIN;
 
     let a = ray.direction.dot(ray.direction);
@@ -30,36 +30,38 @@ impl Object for Sphere {
       IntersectionCollection::from_vec_unchecked(vec![])
     } else {
       IntersectionCollection::from_vec_unchecked(vec![
-        Intersection {
-          t: -(discriminant.sqrt() + b) / (2.0 * a),
-          object: self,
-        },
-        Intersection {
-          t: (discriminant.sqrt() - b) / (2.0 * a),
-          object: self,
-        },
+        Intersection::new(-(discriminant.sqrt() + b) / (2.0 * a), self),
+        Intersection::new((discriminant.sqrt() - b) / (2.0 * a), self),
       ])
     }
   }
 
   /// Returns the normal vector of the surface sphere at the given point
-  fn local_normal_at(&self, object_point: Point) -> Vector {
+  fn local_normal_at(&self, object_point: Point, _: &Intersection) -> Vector {
     object_point - Point::ORIGIN
   }
 
+  fn local_bounds(&self) -> Bounds {
+    Bounds::UNIT
+  }
+
   fn material(&self) -> &Material {
     &self.material
   }
 
-  fn transform(&self) -> &Matrix4x4 {
+  fn transform(&self) -> &Transform {
     &self.transform
   }
+
+  fn set_transform(&mut self, transform: Transform) {
+    self.transform = transform;
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
-  use std::f32::consts::PI;
+  use std::f32::consts::{FRAC_1_SQRT_2, PI};
 
   #[test]
   fn ray_sphere_intersection() {
@@ -128,7 +130,7 @@ mod tests {
     let mut sphere = Sphere::new();
     let transform = Matrix4x4::translation(2.0, 3.0, 4.0);
 
-    sphere.transform = transform.clone();
+    sphere.transform = Transform::new(transform);
     assert!(matches!(sphere.transform, transform));
   }
 
@@ -136,7 +138,7 @@ mod tests {
   fn intersect_scaled_sphere() {
     let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
     let mut sphere = Sphere::new();
-    sphere.transform = Matrix4x4::scale(2.0, 2.0, 2.0);
+    sphere.transform = Transform::new(Matrix4x4::scale(2.0, 2.0, 2.0));
     let xs = sphere.intersect(ray);
 
     assert_eq!(xs.len(), 2);
@@ -148,7 +150,7 @@ mod tests {
   fn intersect_translated_sphere() {
     let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
     let mut sphere = Sphere::new();
-    sphere.transform = Matrix4x4::translation(5.0, 0.0, 0.0);
+    sphere.transform = Transform::new(Matrix4x4::translation(5.0, 0.0, 0.0));
     let xs = sphere.intersect(ray);
 
     assert_eq!(xs.len(), 0);
@@ -157,7 +159,10 @@ mod tests {
   #[test]
   fn normal_on_x_axis() {
     let sphere = Sphere::new();
-    let normal = sphere.normal_at(Point::from((1.0, 0.0, 0.0)));
+    let normal = sphere.normal_at(
+      Point::from((1.0, 0.0, 0.0)),
+      &Intersection::new(0.0, &sphere),
+    );
     let expected = Vector::from((1.0, 0.0, 0.0));
     assert!(normal.approx_eq(expected));
   }
@@ -165,7 +170,10 @@ mod tests {
   #[test]
   fn normal_on_y_axis() {
     let sphere = Sphere::new();
-    let normal = sphere.normal_at(Point::from((0.0, 1.0, 0.0)));
+    let normal = sphere.normal_at(
+      Point::from((0.0, 1.0, 0.0)),
+      &Intersection::new(0.0, &sphere),
+    );
     let expected = Vector::from((0.0, 1.0, 0.0));
     assert!(normal.approx_eq(expected));
   }
@@ -173,7 +181,10 @@ mod tests {
   #[test]
   fn normal_on_z_axis() {
     let sphere = Sphere::new();
-    let normal = sphere.normal_at(Point::from((0.0, 0.0, 1.0)));
+    let normal = sphere.normal_at(
+      Point::from((0.0, 0.0, 1.0)),
+      &Intersection::new(0.0, &sphere),
+    );
     let expected = Vector::from((0.0, 0.0, 1.0));
     assert!(normal.approx_eq(expected));
   }
@@ -181,11 +192,14 @@ mod tests {
   #[test]
   fn normal_nonaxial() {
     let sphere = Sphere::new();
-    let normal = sphere.normal_at(Point::from((
-      1.0 / 3.0f32.sqrt(),
-      1.0 / 3.0f32.sqrt(),
-      1.0 / 3.0f32.sqrt(),
-    )));
+    let normal = sphere.normal_at(
+      Point::from((
+        1.0 / 3.0f32.sqrt(),
+        1.0 / 3.0f32.sqrt(),
+        1.0 / 3.0f32.sqrt(),
+      )),
+      &Intersection::new(0.0, &sphere),
+    );
     let expected = Vector::from((
       1.0 / 3.0f32.sqrt(),
       1.0 / 3.0f32.sqrt(),
@@ -197,11 +211,14 @@ mod tests {
   #[test]
   fn normal_is_unit_length() {
     let sphere = Sphere::new();
-    let normal = sphere.normal_at(Point::from((
-      1.0 / 3.0f32.sqrt(),
-      1.0 / 3.0f32.sqrt(),
-      1.0 / 3.0f32.sqrt(),
-    )));
+    let normal = sphere.normal_at(
+      Point::from((
+        1.0 / 3.0f32.sqrt(),
+        1.0 / 3.0f32.sqrt(),
+        1.0 / 3.0f32.sqrt(),
+      )),
+      &Intersection::new(0.0, &sphere),
+    );
     let expected = normal.normalise();
     assert!(normal.approx_eq(expected));
   }
@@ -209,23 +226,26 @@ mod tests {
   #[test]
   fn normal_translated_sphere() {
     let mut sphere = Sphere::new();
-    sphere.transform = Matrix4x4::translation(0.0, 1.0, 0.0);
+    sphere.transform = Transform::new(Matrix4x4::translation(0.0, 1.0, 0.0));
 
-    let normal = sphere.normal_at(Point::from((0.0, 1.70711, -0.70711)));
-    let expected = Vector::from((0.0, 0.70711, -0.70711));
+    let normal = sphere.normal_at(
+      Point::from((0.0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2)),
+      &Intersection::new(0.0, &sphere),
+    );
+    let expected = Vector::from((0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
     assert!(normal.approx_eq(expected));
   }
 
   #[test]
   fn normal_transform_sphere() {
     let mut sphere = Sphere::new();
-    sphere.transform = Matrix4x4::scale(1.0, 0.5, 1.0) * Matrix4x4::rotation_z(PI / 5.0);
+    sphere.transform =
+      Transform::new(Matrix4x4::scale(1.0, 0.5, 1.0) * Matrix4x4::rotation_z(PI / 5.0));
 
-    let normal = sphere.normal_at(Point::from((
-      0.0,
-      1.0 / 2.0f32.sqrt(),
-      -1.0 / 2.0f32.sqrt(),
-    )));
+    let normal = sphere.normal_at(
+      Point::from((0.0, 1.0 / 2.0f32.sqrt(), -1.0 / 2.0f32.sqrt())),
+      &Intersection::new(0.0, &sphere),
+    );
     let expected = Vector::from((0.0, 0.97014, -0.24254));
     assert!(normal.approx_eq(expected));
   }
@@ -254,4 +274,15 @@ mod tests {
     };
     assert!(matches!(sphere.material, expected));
   }
+
+  #[test]
+  fn bounds_of_transformed_sphere() {
+    let mut sphere = Sphere::new();
+    sphere.transform =
+      Transform::new(Matrix4x4::translation(1.0, -3.0, 5.0) * Matrix4x4::scale(0.5, 2.0, 4.0));
+    let bounds = sphere.bounds();
+
+    assert!(bounds.min.approx_eq(Point::new(0.5, -5.0, 1.0)));
+    assert!(bounds.max.approx_eq(Point::new(1.5, -1.0, 9.0)));
+  }
 }
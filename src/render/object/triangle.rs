@@ -0,0 +1,171 @@
+use crate::*;
+
+/// A flat triangle between three points
+#[derive(Debug)]
+pub struct Triangle {
+  pub material: Material,
+  pub transform: Transform,
+  p1: Point,
+  p2: Point,
+  p3: Point,
+  e1: Vector,
+  e2: Vector,
+  normal: Vector,
+}
+
+impl Triangle {
+  pub fn new(p1: impl Into<Point>, p2: impl Into<Point>, p3: impl Into<Point>) -> Self {
+    let (p1, p2, p3) = (p1.into(), p2.into(), p3.into());
+    let e1 = p2 - p1;
+    let e2 = p3 - p1;
+
+    Triangle {
+      material: Material::default(),
+      transform: Transform::IDENTITY,
+      p1,
+      p2,
+      p3,
+      e1,
+      e2,
+      normal: e2.cross(e1).normalise(),
+    }
+  }
+
+  pub fn points(&self) -> (Point, Point, Point) {
+    (self.p1, self.p2, self.p3)
+  }
+
+  pub fn edges(&self) -> (Vector, Vector) {
+    (self.e1, self.e2)
+  }
+
+  pub fn normal(&self) -> Vector {
+    self.normal
+  }
+}
+
+/// Möller–Trumbore ray-triangle intersection, returning the t value and the barycentric u and v
+/// of the hit
+pub(super) fn intersect_triangle(p1: Point, e1: Vector, e2: Vector, ray: Ray) -> Option<Tuple3> {
+  let dir_cross_e2 = ray.direction.cross(e2);
+  let determinant = e1.dot(dir_cross_e2);
+  // the ray is parallel to the triangle
+  if determinant.abs() < EPSILON {
+    return None;
+  }
+
+  let f = 1.0 / determinant;
+  let p1_to_origin = ray.origin - p1;
+  let u = f * p1_to_origin.dot(dir_cross_e2);
+  if !(0.0..=1.0).contains(&u) {
+    return None;
+  }
+
+  let origin_cross_e1 = p1_to_origin.cross(e1);
+  let v = f * ray.direction.dot(origin_cross_e1);
+  if v < 0.0 || u + v > 1.0 {
+    return None;
+  }
+
+  Some((f * e2.dot(origin_cross_e1), u, v))
+}
+
+impl Object for Triangle {
+  fn local_intersect(&self, ray: Ray) -> IntersectionCollection<'_> {
+    match intersect_triangle(self.p1, self.e1, self.e2, ray) {
+      Some((t, u, v)) => {
+        IntersectionCollection::from_vec_unchecked(vec![Intersection::with_uv(t, self, u, v)])
+      }
+      None => IntersectionCollection::new(),
+    }
+  }
+
+  fn local_normal_at(&self, _: Point, _: &Intersection) -> Vector {
+    self.normal
+  }
+
+  fn local_bounds(&self) -> Bounds {
+    let mut bounds = Bounds::EMPTY;
+    for point in [self.p1, self.p2, self.p3] {
+      bounds.add_point(point);
+    }
+    bounds
+  }
+
+  fn material(&self) -> &Material {
+    &self.material
+  }
+
+  fn transform(&self) -> &Transform {
+    &self.transform
+  }
+
+  fn set_transform(&mut self, transform: Transform) {
+    self.transform = transform;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn test_triangle() -> Triangle {
+    Triangle::new((0.0, 1.0, 0.0), (-1.0, 0.0, 0.0), (1.0, 0.0, 0.0))
+  }
+
+  #[test]
+  fn construct_triangle() {
+    let triangle = test_triangle();
+    let (e1, e2) = triangle.edges();
+
+    assert!(e1.approx_eq(Vector::new(-1.0, -1.0, 0.0)));
+    assert!(e2.approx_eq(Vector::new(1.0, -1.0, 0.0)));
+    assert!(triangle.normal().approx_eq(Vector::new(0.0, 0.0, -1.0)));
+  }
+
+  #[test]
+  fn triangle_normal_is_constant() {
+    let triangle = test_triangle();
+    let hit = Intersection::new(0.0, &triangle);
+
+    for point in [(0.0, 0.5, 0.0), (-0.5, 0.75, 0.0), (0.5, 0.25, 0.0)] {
+      let normal = triangle.local_normal_at(point.into(), &hit);
+      assert!(normal.approx_eq(triangle.normal()));
+    }
+  }
+
+  #[test]
+  fn ray_parallel_to_triangle() {
+    let triangle = test_triangle();
+    let ray = Ray::new((0.0, -1.0, -2.0), (0.0, 1.0, 0.0));
+    assert!(triangle.local_intersect(ray).is_empty());
+  }
+
+  #[test]
+  fn ray_misses_triangle_edges() {
+    let triangle = test_triangle();
+    for origin in [(1.0, 1.0, -2.0), (-1.0, 1.0, -2.0), (0.0, -1.0, -2.0)] {
+      let ray = Ray::new(origin, (0.0, 0.0, 1.0));
+      assert!(triangle.local_intersect(ray).is_empty());
+    }
+  }
+
+  #[test]
+  fn ray_strikes_triangle() {
+    let triangle = test_triangle();
+    let ray = Ray::new((0.0, 0.5, -2.0), (0.0, 0.0, 1.0));
+    let intersections = triangle.local_intersect(ray);
+
+    assert_eq!(intersections.len(), 1);
+    assert!(intersections[0].t.approx_eq(2.0));
+  }
+
+  #[test]
+  fn triangle_bounds() {
+    let triangle = Triangle::new((-3.0, 7.0, 2.0), (6.0, 2.0, -4.0), (2.0, -1.0, -1.0));
+    let bounds = triangle.local_bounds();
+
+    assert!(bounds.min.approx_eq(Point::new(-3.0, -1.0, -4.0)));
+    assert!(bounds.max.approx_eq(Point::new(6.0, 7.0, 2.0)));
+  }
+}
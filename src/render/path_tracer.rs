@@ -0,0 +1,262 @@
+use crate::*;
+use std::f32::consts::PI;
+
+/// Monte Carlo path tracer, for global illumination such as colour bleeding and indirect light.
+///
+/// Every hit follows a single bounce chosen at random in proportion to the material: a mirror
+/// reflection with probability `reflective`, a refraction with probability `transparency`, and a
+/// cosine-weighted diffuse bounce otherwise. Diffuse hits also sample every light directly (next
+/// event estimation). The background acts as the only emitter for rays that escape, except after
+/// a diffuse bounce when a light already samples it (see [`Light::samples_background`]), and
+/// `ambient` is ignored since indirect light takes its place.
+#[derive(Debug, Clone)]
+pub struct PathTracer {
+  /// Maximum number of bounces along a path
+  pub max_depth: usize,
+  /// Number of bounces before Russian roulette may end a path early
+  pub min_depth: usize,
+}
+
+impl Default for PathTracer {
+  fn default() -> Self {
+    PathTracer {
+      max_depth: 8,
+      min_depth: 3,
+    }
+  }
+}
+
+impl PathTracer {
+  pub fn new(max_depth: usize) -> Self {
+    PathTracer {
+      max_depth,
+      ..Default::default()
+    }
+  }
+}
+
+impl Integrator for PathTracer {
+  /// A single-sample estimate of the light arriving along the ray
+  fn colour_at(&self, world: &World, ray: Ray, rng: &mut Rng) -> Colour {
+    let mut colour = Colour::BLACK;
+    let mut throughput = Colour::WHITE;
+    let mut ray = ray;
+    let mut after_diffuse = false;
+    let background_sampled = world
+      .lights
+      .iter()
+      .any(|light| light.samples_background(&world.background));
+
+    for depth in 0..self.max_depth {
+      let intersections = world.intersect(ray);
+      let Some(hit) = intersections.hit() else {
+        if after_diffuse && background_sampled {
+          return colour;
+        }
+        return colour + throughput * world.background.colour_at(ray.direction);
+      };
+      let computations = hit.prepare_computations_with(ray, &intersections);
+      let material = computations.object.material();
+
+      let choice = rng.next_f32();
+      let reflective = material.reflective.max(0.0);
+      let transparency = material.transparency.max(0.0);
+      after_diffuse = choice >= reflective + transparency;
+      if choice < reflective {
+        ray = Ray::new(computations.over_position, computations.reflect);
+      } else if choice < reflective + transparency {
+        ray = refract(&computations, rng);
+      } else {
+        let local_position = computations
+          .object
+          .world_to_object(computations.over_position);
+        for light in &world.lights {
+          let samples = light.visible_samples(computations.over_position, world);
+          colour = colour
+            + throughput
+              * material.sample_lighting(
+                light.as_ref(),
+                &samples,
+                computations.eye,
+                computations.normal,
+                local_position,
+              );
+        }
+
+        // The cosine term and the pdf of cosine-weighted sampling cancel out, leaving the albedo
+        throughput = throughput * material.pattern.colour_at(local_position) * material.diffuse;
+        let direction = cosine_sample_hemisphere(computations.normal, rng);
+        ray = Ray::new(computations.over_position, direction);
+      }
+
+      if depth + 1 >= self.min_depth {
+        let survival = throughput
+          .red
+          .max(throughput.green)
+          .max(throughput.blue)
+          .min(0.95);
+        if survival <= 0.0 || rng.next_f32() >= survival {
+          break;
+        }
+        throughput = throughput * (1.0 / survival);
+      }
+    }
+
+    colour
+  }
+}
+
+/// Ray continuing through a transparent surface, or reflected off it with the probability given
+/// by the Schlick approximation (always, on total internal reflection)
+fn refract(computations: &IntersectionComputations, rng: &mut Rng) -> Ray {
+  let reflect = Ray::new(computations.over_position, computations.reflect);
+  if rng.next_f32() < computations.schlick() {
+    return reflect;
+  }
+
+  let n_ratio = computations.n1 / computations.n2;
+  let cos_i = computations.eye.dot(computations.normal);
+  let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+  if sin2_t > 1.0 {
+    return reflect;
+  }
+  let cos_t = (1.0 - sin2_t).sqrt();
+  let direction = computations.normal * (n_ratio * cos_i - cos_t) - computations.eye * n_ratio;
+  Ray::new(computations.under_position, direction)
+}
+
+/// Random direction in the hemisphere around `normal`, more likely close to the normal in
+/// proportion to the cosine of the angle between them
+fn cosine_sample_hemisphere(normal: Vector, rng: &mut Rng) -> Vector {
+  let radius = rng.next_f32().sqrt();
+  let phi = 2.0 * PI * rng.next_f32();
+  let (x, y) = (radius * phi.cos(), radius * phi.sin());
+  let z = (1.0 - radius * radius).max(0.0).sqrt();
+
+  // Orthonormal basis around the normal
+  let helper = if normal.x.abs() > 0.9 {
+    Vector::new(0.0, 1.0, 0.0)
+  } else {
+    Vector::new(1.0, 0.0, 0.0)
+  };
+  let tangent = helper.cross(normal).normalise();
+  let bitangent = normal.cross(tangent);
+  (tangent * x + bitangent * y + normal * z).normalise()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::{DynamicImage, ImageBuffer, Rgb};
+
+  #[test]
+  fn cosine_samples_lie_in_hemisphere() {
+    let mut rng = Rng::new(7);
+    let normal = Vector::new(0.0, 0.0, -1.0);
+    let mut sum = 0.0;
+    for _ in 0..1000 {
+      let direction = cosine_sample_hemisphere(normal, &mut rng);
+      assert!(direction.magnitude().approx_eq(1.0));
+      assert!(direction.dot(normal) >= 0.0);
+      sum += direction.dot(normal);
+    }
+    // The mean cosine of a cosine-weighted hemisphere is 2/3
+    assert!((sum / 1000.0 - 2.0 / 3.0).abs() < 0.03);
+  }
+
+  #[test]
+  fn ray_missing_everything_sees_background() {
+    let mut world = World::default();
+    world.background = Background::Solid(Colour::new(0.2, 0.4, 0.6));
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 1.0, 0.0));
+    let colour = PathTracer::default().colour_at(&world, ray, &mut Rng::new(1));
+    assert!(colour.approx_eq(Colour::new(0.2, 0.4, 0.6)));
+  }
+
+  #[test]
+  fn diffuse_sphere_in_uniform_sky() {
+    // Light bouncing off a convex object always escapes to the sky after one bounce
+    let mut world = World::new();
+    world.background = Background::Solid(Colour::WHITE);
+    let mut sphere = Sphere::new();
+    sphere.material.diffuse = 0.5;
+    world.add_object(Box::new(sphere));
+
+    let tracer = PathTracer::default();
+    let mut rng = Rng::new(3);
+    for _ in 0..16 {
+      let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+      let colour = tracer.colour_at(&world, ray, &mut rng);
+      assert!(colour.approx_eq(Colour::new(0.5, 0.5, 0.5)));
+    }
+  }
+
+  #[test]
+  fn diffuse_sphere_in_environment_furnace() {
+    // The environment reaches the sphere through the light alone, not again on escaping rays
+    let image = ImageBuffer::from_pixel(32, 16, Rgb([1.0f32, 1.0, 1.0]));
+    let map = EnvironmentMap::from_image(&DynamicImage::ImageRgb32F(image)).unwrap();
+    let mut world = World::new();
+    world.background = Background::Environment(map.clone());
+    world
+      .lights
+      .push(Box::new(EnvironmentLight::new(map, 1024)));
+    let mut sphere = Sphere::new();
+    sphere.material.diffuse = 0.5;
+    sphere.material.specular = 0.0;
+    world.add_object(Box::new(sphere));
+
+    let tracer = PathTracer::default();
+    let mut rng = Rng::new(5);
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let colour = (0..64).fold(Colour::BLACK, |sum, _| {
+      sum + tracer.colour_at(&world, ray, &mut rng)
+    }) * (1.0 / 64.0);
+    assert!((colour.red - 0.5).abs() < 0.05, "{colour:?}");
+  }
+
+  #[test]
+  fn environment_light_keeps_different_background() {
+    // The light gives nothing, so all the light comes from the background after the bounce
+    let image = ImageBuffer::from_pixel(4, 2, Rgb([0.0f32, 0.0, 0.0]));
+    let map = EnvironmentMap::from_image(&DynamicImage::ImageRgb32F(image)).unwrap();
+    let mut world = World::new();
+    world.background = Background::Solid(Colour::WHITE);
+    world.lights.push(Box::new(EnvironmentLight::new(map, 4)));
+    let mut sphere = Sphere::new();
+    sphere.material.diffuse = 0.5;
+    world.add_object(Box::new(sphere));
+
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let colour = PathTracer::default().colour_at(&world, ray, &mut Rng::new(3));
+    assert!(colour.approx_eq(Colour::new(0.5, 0.5, 0.5)));
+  }
+
+  #[test]
+  fn indirect_light_bleeds_colour() {
+    let mut world = World::new();
+    let mut floor = Plane::new();
+    floor.material.specular = 0.0;
+    let mut wall = Plane::new();
+    wall.transform =
+      Transform::new(Matrix4x4::translation(1.0, 0.0, 0.0) * Matrix4x4::rotation_z(PI / 2.0));
+    wall.material.pattern = Pattern::solid(Colour::RED);
+    wall.material.specular = 0.0;
+    world.add_object(Box::new(floor));
+    world.add_object(Box::new(wall));
+    world
+      .lights
+      .push(Box::new(PointLight::new((-5.0, 10.0, 0.0), Colour::WHITE)));
+
+    let tracer = PathTracer::default();
+    let mut rng = Rng::new(11);
+    let ray = Ray::new((0.5, 1.0, 0.0), (0.0, -1.0, 0.0));
+    let colour = (0..256).fold(Colour::BLACK, |sum, _| {
+      sum + tracer.colour_at(&world, ray, &mut rng)
+    }) * (1.0 / 256.0);
+
+    // The floor is white and so is the light, so any tint comes from the red wall
+    assert!(colour.red > colour.green * 1.1);
+    assert!(colour.green.approx_eq(colour.blue));
+  }
+}
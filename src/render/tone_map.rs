@@ -0,0 +1,204 @@
+use crate::*;
+use image::Rgb;
+
+/// Curve used to bring unbounded linear colours into `[0, 1]` for 8-bit output.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ToneMapOperator {
+  /// Clip every channel to `[0, 1]`, as the renderer has always done
+  #[default]
+  Clamp,
+  /// `x / (1 + x)`: never clips, but flattens contrast in the highlights
+  Reinhard,
+  /// Krzysztof Narkowicz's fit of the ACES filmic curve, with a gentle toe and shoulder
+  Aces,
+  /// John Hable's filmic curve from Uncharted 2, normalised so that a linear value of 11.2 maps
+  /// to white
+  Uncharted2,
+}
+
+/// Encoding of tone-mapped colours in 8-bit output.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TransferFunction {
+  /// The sRGB curve expected by displays and image viewers
+  #[default]
+  Srgb,
+  /// Linear values written as they are, as the renderer used to do
+  Linear,
+}
+
+impl TransferFunction {
+  pub fn encode(self, colour: Colour) -> Colour {
+    match self {
+      TransferFunction::Srgb => colour.linear_to_srgb(),
+      TransferFunction::Linear => colour,
+    }
+  }
+
+  pub fn decode(self, colour: Colour) -> Colour {
+    match self {
+      TransferFunction::Srgb => colour.srgb_to_linear(),
+      TransferFunction::Linear => colour,
+    }
+  }
+}
+
+/// Exposure adjustment followed by a tone-mapping curve, applied per channel when converting
+/// linear colours to 8-bit, and the transfer function used to encode the result.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ToneMapping {
+  /// Exposure in stops: each stop doubles the brightness before the curve is applied
+  pub exposure: f32,
+  pub operator: ToneMapOperator,
+  pub transfer: TransferFunction,
+}
+
+impl ToneMapping {
+  /// Clamped, linear output, matching images rendered before sRGB encoding was added
+  pub const LEGACY: ToneMapping = ToneMapping {
+    exposure: 0.0,
+    operator: ToneMapOperator::Clamp,
+    transfer: TransferFunction::Linear,
+  };
+
+  pub fn new(operator: ToneMapOperator) -> Self {
+    ToneMapping {
+      operator,
+      ..Default::default()
+    }
+  }
+
+  pub fn with_exposure(mut self, exposure: f32) -> Self {
+    self.exposure = exposure;
+    self
+  }
+
+  pub fn with_transfer(mut self, transfer: TransferFunction) -> Self {
+    self.transfer = transfer;
+    self
+  }
+
+  /// Tone maps and encodes a linear colour as 8 bits per channel
+  pub fn to_rgb8(&self, colour: Colour) -> Rgb<u8> {
+    let colour = self.transfer.encode(self.apply(colour));
+    Rgb(
+      [colour.red, colour.green, colour.blue]
+        .map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8),
+    )
+  }
+
+  /// Maps a linear colour to a linear colour with every channel in `[0, 1]`
+  pub fn apply(&self, colour: Colour) -> Colour {
+    let scale = self.exposure.exp2();
+    let map = |value: f32| {
+      let value = (value * scale).max(0.0);
+      if value.is_infinite() {
+        return 1.0;
+      }
+      let mapped = match self.operator {
+        ToneMapOperator::Clamp => value,
+        ToneMapOperator::Reinhard => value / (1.0 + value),
+        ToneMapOperator::Aces => {
+          (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)
+        }
+        ToneMapOperator::Uncharted2 => hable(value) / hable(UNCHARTED2_WHITE),
+      };
+      mapped.clamp(0.0, 1.0)
+    };
+    Colour::new(map(colour.red), map(colour.green), map(colour.blue))
+  }
+}
+
+const UNCHARTED2_WHITE: f32 = 11.2;
+
+fn hable(x: f32) -> f32 {
+  const A: f32 = 0.15; // shoulder strength
+  const B: f32 = 0.50; // linear strength
+  const C: f32 = 0.10; // linear angle
+  const D: f32 = 0.20; // toe strength
+  const E: f32 = 0.02; // toe numerator
+  const F: f32 = 0.30; // toe denominator
+  (x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F) - E / F
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const OPERATORS: [ToneMapOperator; 4] = [
+    ToneMapOperator::Clamp,
+    ToneMapOperator::Reinhard,
+    ToneMapOperator::Aces,
+    ToneMapOperator::Uncharted2,
+  ];
+
+  #[test]
+  fn default_clamps() {
+    let colour = Colour::new(1.5, 0.25, -0.5);
+    let result = ToneMapping::default().apply(colour);
+    assert!(result.approx_eq(Colour::new(1.0, 0.25, 0.0)));
+  }
+
+  #[test]
+  fn exposure_in_stops() {
+    let tone_mapping = ToneMapping::default().with_exposure(1.0);
+    let result = tone_mapping.apply(Colour::new(0.25, 0.1, 0.6));
+    assert!(result.approx_eq(Colour::new(0.5, 0.2, 1.0)));
+
+    let tone_mapping = ToneMapping::default().with_exposure(-2.0);
+    let result = tone_mapping.apply(Colour::new(2.0, 0.0, 0.0));
+    assert!(result.approx_eq(Colour::new(0.5, 0.0, 0.0)));
+  }
+
+  #[test]
+  fn rgb8_encoding() {
+    let colour = Colour::new(0.5, 2.0, 0.0);
+    assert_eq!(ToneMapping::default().to_rgb8(colour), Rgb([188, 255, 0]));
+    assert_eq!(ToneMapping::LEGACY.to_rgb8(colour), Rgb([128, 255, 0]));
+  }
+
+  #[test]
+  fn reinhard() {
+    let tone_mapping = ToneMapping::new(ToneMapOperator::Reinhard);
+    let result = tone_mapping.apply(Colour::new(1.0, 3.0, 0.0));
+    assert!(result.approx_eq(Colour::new(0.5, 0.75, 0.0)));
+  }
+
+  #[test]
+  fn uncharted2_maps_white_point_to_white() {
+    let tone_mapping = ToneMapping::new(ToneMapOperator::Uncharted2);
+    let white = Colour::new(UNCHARTED2_WHITE, UNCHARTED2_WHITE, UNCHARTED2_WHITE);
+    assert!(tone_mapping.apply(white).approx_eq(Colour::WHITE));
+    assert!(tone_mapping.apply(Colour::BLACK).approx_eq(Colour::BLACK));
+  }
+
+  #[test]
+  fn highlights_roll_off() {
+    for operator in OPERATORS.into_iter().skip(1) {
+      let tone_mapping = ToneMapping::new(operator);
+      let mut previous = tone_mapping.apply(Colour::BLACK).red;
+      for value in [0.1, 0.5, 1.0, 2.0, 4.0] {
+        let mapped = tone_mapping.apply(Colour::new(value, value, value)).red;
+        assert!(
+          mapped > previous,
+          "{operator:?} is not increasing at {value}"
+        );
+        assert!(mapped < 1.0, "{operator:?} clips at {value}");
+        previous = mapped;
+      }
+    }
+  }
+
+  #[test]
+  fn results_are_in_unit_range() {
+    for operator in OPERATORS {
+      let tone_mapping = ToneMapping::new(operator);
+      for value in [-1.0, 0.0, 0.5, 1.0, 100.0, f32::INFINITY] {
+        let mapped = tone_mapping.apply(Colour::new(value, value, value)).red;
+        assert!(
+          (0.0..=1.0).contains(&mapped),
+          "{operator:?} maps {value} to {mapped}"
+        );
+      }
+    }
+  }
+}
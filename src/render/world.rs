@@ -1,10 +1,15 @@
 use crate::*;
+use std::sync::OnceLock;
 
 pub static MAX_RECURSION_DEPTH: usize = 5;
 
 pub struct World {
-  pub objects: Vec<Box<dyn Object>>,
-  pub lights: Vec<PointLight>,
+  objects: Vec<Box<dyn Object>>,
+  pub lights: Vec<Box<dyn Light>>,
+  /// Seen by rays that miss every object, including reflected and refracted rays
+  pub background: Background,
+  /// Built by the first intersection test after the objects change
+  bvh: OnceLock<Bvh>,
 }
 
 impl World {
@@ -12,35 +17,73 @@ impl World {
     World {
       objects: vec![],
       lights: vec![],
+      background: Background::default(),
+      bvh: OnceLock::new(),
     }
   }
 
-  pub fn intersect(&self, ray: Ray) -> IntersectionCollection {
-    let mut intersections = IntersectionCollection::new();
-    self
-      .objects
-      .iter()
-      .fold(&mut intersections, |acc, x| acc.merge(x.intersect(ray)));
-    intersections
+  pub fn objects(&self) -> &[Box<dyn Object>] {
+    &self.objects
   }
 
-  pub fn shade_hit(&self, computations: &IntersectionComputations, remaining: usize) -> Colour {
-    let shadowed = self.is_shadowed(computations.over_position);
-    let local_position =
-      computations.object.transform().inverse().unwrap() * computations.over_position;
-
-    let surface_colour = computations.object.material().lighting(
-      &self.lights[0],
-      computations.over_position,
-      computations.eye,
-      computations.normal,
-      shadowed,
-      local_position,
-    );
+  /// Mutable access to the objects, which discards the bounding volume hierarchy since any of them
+  /// may be moved, replaced or removed
+  pub fn objects_mut(&mut self) -> &mut Vec<Box<dyn Object>> {
+    self.bvh.take();
+    &mut self.objects
+  }
+
+  /// Adds an object, discarding the bounding volume hierarchy
+  pub fn add_object(&mut self, object: Box<dyn Object>) {
+    self.objects_mut().push(object);
+  }
 
+  /// Builds the bounding volume hierarchy over the objects now, rather than on the first
+  /// intersection test after they change
+  pub fn build_bvh(&self) -> &Bvh {
+    self.bvh.get_or_init(|| Bvh::build(&self.objects))
+  }
+
+  pub fn intersect(&self, ray: Ray) -> IntersectionCollection<'_> {
+    self.build_bvh().intersect(&self.objects, ray)
+  }
+
+  /// Returns the colour at the intersection, summing the contribution of every light in the world
+  pub fn shade_hit(&self, computations: &IntersectionComputations, remaining: usize) -> Colour {
+    let surface_colour = self.direct_lighting(computations);
     let reflected_colour = self.reflected_colour(computations, remaining);
+    let refracted_colour = self.refracted_colour(computations, remaining);
 
-    surface_colour + reflected_colour
+    let material = computations.object.material();
+    if material.reflective > 0.0 && material.transparency > 0.0 {
+      let reflectance = computations.schlick();
+      surface_colour + reflected_colour * reflectance + refracted_colour * (1.0 - reflectance)
+    } else {
+      surface_colour + reflected_colour + refracted_colour
+    }
+  }
+
+  /// Returns the colour of the surface itself at the intersection, lit by every light in the
+  /// world, without reflections or refractions
+  pub fn direct_lighting(&self, computations: &IntersectionComputations) -> Colour {
+    let local_position = computations
+      .object
+      .world_to_object(computations.over_position);
+
+    let material = computations.object.material();
+
+    self.lights.iter().fold(Colour::BLACK, |acc, light| {
+      let samples = light.visible_samples(computations.over_position, self);
+      acc
+        + material.ambient_lighting(light.as_ref(), local_position)
+        + material.sample_lighting(
+          light.as_ref(),
+          &samples,
+          computations.eye,
+          computations.normal,
+          local_position,
+        )
+    })
   }
 
   pub fn colour_at(&self, ray: Ray) -> Colour {
@@ -50,22 +93,21 @@ impl World {
   pub fn _colour_at(&self, ray: Ray, remaining: usize) -> Colour {
     let intersections = self.intersect(ray);
     if let Some(hit) = intersections.hit() {
-      let computations = hit.prepare_computations(ray);
+      let computations = hit.prepare_computations_with(ray, &intersections);
       self.shade_hit(&computations, remaining)
     } else {
-      Colour::BLACK
+      self.background.colour_at(ray.direction)
     }
   }
 
-  pub fn is_shadowed(&self, position: Point) -> bool {
-    let point_to_light = self.lights[0].position - position;
-    let distance = point_to_light.magnitude();
-    let ray = Ray::new(position, point_to_light.normalise());
+  /// Returns whether the light sample is occluded from the position by any object in the world
+  pub fn is_shadowed(&self, position: Point, sample: &LightSample) -> bool {
+    let ray = Ray::new(position, sample.direction);
 
     self
       .intersect(ray)
       .hit()
-      .map_or(false, |hit| hit.t < distance)
+      .is_some_and(|hit| hit.t < sample.distance)
   }
 
   pub fn reflected_colour(
@@ -81,14 +123,40 @@ impl World {
 
     colour.clamp(0.0, 1.0) * computations.object.material().reflective
   }
+
+  pub fn refracted_colour(
+    &self,
+    computations: &IntersectionComputations,
+    remaining: usize,
+  ) -> Colour {
+    let transparency = computations.object.material().transparency;
+    if remaining == 0 || transparency == 0.0 {
+      return Colour::BLACK;
+    }
+
+    // Snell's law
+    let n_ratio = computations.n1 / computations.n2;
+    let cos_i = computations.eye.dot(computations.normal);
+    let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+    if sin2_t > 1.0 {
+      // total internal reflection
+      return Colour::BLACK;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let direction = computations.normal * (n_ratio * cos_i - cos_t) - computations.eye * n_ratio;
+    let refract_ray = Ray::new(computations.under_position, direction);
+
+    self._colour_at(refract_ray, remaining - 1) * transparency
+  }
 }
 
 impl Default for World {
   fn default() -> World {
     let mut world = World::new();
-    world
-      .lights
-      .push(PointLight::new((-10.0, 10.0, -10.0), (1.0, 1.0, 1.0)));
+    world.lights.push(Box::new(PointLight::new(
+      (-10.0, 10.0, -10.0),
+      (1.0, 1.0, 1.0),
+    )));
 
     let mut sphere_1 = Sphere::new();
     sphere_1.material = Material {
@@ -98,9 +166,9 @@ impl Default for World {
       ..Default::default()
     };
     let mut sphere_2 = Sphere::new();
-    sphere_2.transform = Matrix4x4::scale(0.5, 0.5, 0.5);
-    world.objects.push(Box::new(sphere_1));
-    world.objects.push(Box::new(sphere_2));
+    sphere_2.transform = Transform::new(Matrix4x4::scale(0.5, 0.5, 0.5));
+    world.add_object(Box::new(sphere_1));
+    world.add_object(Box::new(sphere_2));
 
     world
   }
@@ -109,12 +177,14 @@ impl Default for World {
 #[cfg(test)]
 mod tests {
   use super::*;
+  use image::{DynamicImage, ImageBuffer, Rgb};
+  use std::f32::consts::PI;
 
   #[test]
   fn construct_world() {
     let world = World::new();
 
-    let n_objects = world.objects.len();
+    let n_objects = world.objects().len();
     let n_lights = world.lights.len();
 
     assert_eq!(n_objects, 0);
@@ -125,20 +195,26 @@ mod tests {
   fn default_world() {
     let world = World::default();
 
-    let sphere_1 = &world.objects[0];
-    assert!(sphere_1.transform().approx_eq(Matrix4x4::IDENTITY));
+    let sphere_1 = &world.objects()[0];
+    assert!(sphere_1.transform().matrix().approx_eq(Matrix4x4::IDENTITY));
     assert!(sphere_1.material().diffuse.approx_eq(0.7));
     assert!(sphere_1.material().specular.approx_eq(0.2));
     let _expected = Pattern::solid(Colour::new(0.8, 1.0, 0.6));
     assert!(matches!(&sphere_1.material().pattern, _expected));
 
-    let sphere_2 = &world.objects[1];
+    let sphere_2 = &world.objects()[1];
     assert!(sphere_2
       .transform()
+      .matrix()
       .approx_eq(Matrix4x4::scale(0.5, 0.5, 0.5)));
 
     let light = &world.lights[0];
-    assert!(light.approx_eq(PointLight::new((-10.0, 10.0, -10.0), (1.0, 1.0, 1.0))));
+    assert!(light.intensity().approx_eq(Colour::WHITE));
+    let sample = light.samples(Point::ORIGIN)[0];
+    assert!(sample
+      .direction
+      .approx_eq(Vector::new(-1.0, 1.0, -1.0).normalise()));
+    assert!(sample.distance.approx_eq(300.0f32.sqrt()));
   }
 
   #[test]
@@ -154,14 +230,67 @@ mod tests {
     assert!(intersections[3].t.approx_eq(6.0));
   }
 
+  #[test]
+  fn intersect_world_with_bvh() {
+    let mut world = World::default();
+    let mut plane = Plane::new();
+    plane.transform =
+      Transform::new(Matrix4x4::translation(0.0, 0.0, 10.0) * Matrix4x4::rotation_x(PI / 2.0));
+    world.add_object(Box::new(plane));
+    for x in 1..10 {
+      let mut sphere = Sphere::new();
+      sphere.transform = Transform::new(Matrix4x4::translation(x as f32 * 3.0, 0.0, 0.0));
+      world.add_object(Box::new(sphere));
+    }
+    world.build_bvh();
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let intersections = world.intersect(ray);
+
+    assert_eq!(intersections.len(), 5);
+    assert!(intersections[0].t.approx_eq(4.0));
+    assert!(intersections[3].t.approx_eq(6.0));
+    assert!(intersections[4].t.approx_eq(15.0));
+  }
+
+  #[test]
+  fn rendering_builds_bvh() {
+    let mut world = World::new();
+    for x in 0..4 {
+      let mut sphere = Sphere::new();
+      sphere.transform = Transform::new(Matrix4x4::translation(x as f32 * 3.0, 0.0, 0.0));
+      world.add_object(Box::new(sphere));
+    }
+    assert!(world.bvh.get().is_none());
+
+    Camera::new(4, 4, PI / 2.0).render(&world);
+    assert_eq!(world.bvh.get().map(Bvh::len), Some(4));
+
+    world.add_object(Box::new(Sphere::new()));
+    assert!(world.bvh.get().is_none());
+  }
+
+  #[test]
+  fn changing_objects_discards_bvh() {
+    let mut world = World::default();
+    world.build_bvh();
+    let ray = Ray::new((10.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    assert!(world.intersect(ray).is_empty());
+
+    world.objects_mut()[0].set_transform(Transform::new(Matrix4x4::translation(10.0, 0.0, 0.0)));
+    assert_eq!(world.intersect(ray).len(), 2);
+
+    world.build_bvh();
+    let mut sphere = Sphere::new();
+    sphere.transform = Transform::new(Matrix4x4::translation(10.0, 0.0, 5.0));
+    world.add_object(Box::new(sphere));
+    assert_eq!(world.intersect(ray).len(), 4);
+  }
+
   #[test]
   fn precomputing_intersection_state() {
     let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
     let shape = Sphere::new();
-    let intersection = Intersection {
-      t: 4.0,
-      object: &shape,
-    };
+    let intersection = Intersection::new(4.0, &shape);
 
     let comps = intersection.prepare_computations(ray);
 
@@ -175,11 +304,8 @@ mod tests {
   fn shading_intersection() {
     let world = World::default();
     let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
-    let shape = &world.objects[0];
-    let intersection = Intersection {
-      t: 4.0,
-      object: &**shape,
-    };
+    let shape = &world.objects()[0];
+    let intersection = Intersection::new(4.0, &**shape);
     let computations = &intersection.prepare_computations(ray);
 
     let colour = world.shade_hit(computations, 0);
@@ -190,13 +316,10 @@ mod tests {
   #[test]
   fn shading_intersection_from_inside() {
     let mut world = World::default();
-    world.lights[0] = PointLight::new((0.0, 0.25, 0.0), (1.0, 1.0, 1.0));
+    world.lights[0] = Box::new(PointLight::new((0.0, 0.25, 0.0), (1.0, 1.0, 1.0)));
     let ray = Ray::new((0.0, 0.0, 0.0), (0.0, 0.0, 1.0));
-    let shape = &world.objects[1];
-    let intersection = Intersection {
-      t: 0.5,
-      object: &**shape,
-    };
+    let shape = &world.objects()[1];
+    let intersection = Intersection::new(0.5, &**shape);
     let computations = intersection.prepare_computations(ray);
     let colour = world.shade_hit(&computations, 0);
     eprintln!("{colour:?}");
@@ -224,9 +347,10 @@ mod tests {
   fn colour_with_intersection_behind_ray() {
     let (world, inner_colour) = {
       let mut world = World::new();
-      world
-        .lights
-        .push(PointLight::new((-10.0, 10.0, -10.0), (1.0, 1.0, 1.0)));
+      world.lights.push(Box::new(PointLight::new(
+        (-10.0, 10.0, -10.0),
+        (1.0, 1.0, 1.0),
+      )));
 
       let mut outer = Sphere::new();
       outer.material = Material {
@@ -237,7 +361,7 @@ mod tests {
         ..Default::default()
       };
       let mut inner = Sphere::new();
-      inner.transform = Matrix4x4::scale(0.5, 0.5, 0.5);
+      inner.transform = Transform::new(Matrix4x4::scale(0.5, 0.5, 0.5));
       let inner_colour = Colour::new(0.2, 0.3, 0.4);
       inner.material = Material {
         ambient: 1.0,
@@ -245,8 +369,8 @@ mod tests {
         ..Default::default()
       };
 
-      world.objects.push(Box::new(outer));
-      world.objects.push(Box::new(inner));
+      world.add_object(Box::new(outer));
+      world.add_object(Box::new(inner));
       (world, inner_colour)
     };
     let ray = Ray::new((0.0, 0.0, 0.75), (0.0, 0.0, -1.0));
@@ -260,7 +384,8 @@ mod tests {
     let world = World::default();
     let position = Point::new(0.0, 10.0, 0.0);
 
-    assert!(!world.is_shadowed(position));
+    let sample = LightSample::between(position, Point::new(-10.0, 10.0, -10.0), Colour::WHITE);
+    assert!(!world.is_shadowed(position, &sample));
   }
 
   #[test]
@@ -268,7 +393,8 @@ mod tests {
     let world = World::default();
     let position = Point::new(10.0, -10.0, 10.0);
 
-    assert!(world.is_shadowed(position));
+    let sample = LightSample::between(position, Point::new(-10.0, 10.0, -10.0), Colour::WHITE);
+    assert!(world.is_shadowed(position, &sample));
   }
 
   #[test]
@@ -276,7 +402,8 @@ mod tests {
     let world = World::default();
     let position = Point::new(-20.0, 20.0, -20.0);
 
-    assert!(!world.is_shadowed(position));
+    let sample = LightSample::between(position, Point::new(-10.0, 10.0, -10.0), Colour::WHITE);
+    assert!(!world.is_shadowed(position, &sample));
   }
 
   #[test]
@@ -284,26 +411,25 @@ mod tests {
     let world = World::default();
     let position = Point::new(-2.0, 2.0, -2.0);
 
-    assert!(!world.is_shadowed(position));
+    let sample = LightSample::between(position, Point::new(-10.0, 10.0, -10.0), Colour::WHITE);
+    assert!(!world.is_shadowed(position, &sample));
   }
 
   #[test]
   fn shading_shadow() {
     let mut world = World::new();
-    world
-      .lights
-      .push(PointLight::new((0.0, 0.0, -10.0), (1.0, 1.0, 1.0)));
+    world.lights.push(Box::new(PointLight::new(
+      (0.0, 0.0, -10.0),
+      (1.0, 1.0, 1.0),
+    )));
     let sphere_1 = Box::new(Sphere::new());
     let mut sphere_2 = Box::new(Sphere::new());
-    sphere_2.transform = Matrix4x4::translation(0.0, 0.0, 10.0);
-    world.objects.push(sphere_1);
-    world.objects.push(sphere_2);
+    sphere_2.transform = Transform::new(Matrix4x4::translation(0.0, 0.0, 10.0));
+    world.add_object(sphere_1);
+    world.add_object(sphere_2);
 
     let ray = Ray::new((0.0, 0.0, 5.0), (0.0, 0.0, 1.0));
-    let intersection = Intersection {
-      t: 4.0,
-      object: &*world.objects[1],
-    };
+    let intersection = Intersection::new(4.0, &*world.objects()[1]);
     let computations = intersection.prepare_computations(ray);
 
     let colour = world.shade_hit(&computations, 0);
@@ -311,13 +437,88 @@ mod tests {
     assert!(colour.approx_eq(expected));
   }
 
+  #[test]
+  fn shading_intersection_with_multiple_lights() {
+    let single_light = {
+      let world = World::default();
+      let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+      let intersection = Intersection::new(4.0, &*world.objects()[0]);
+      world.shade_hit(&intersection.prepare_computations(ray), 0)
+    };
+
+    let mut world = World::default();
+    world.lights.push(Box::new(PointLight::new(
+      (-10.0, 10.0, -10.0),
+      (1.0, 1.0, 1.0),
+    )));
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let intersection = Intersection::new(4.0, &*world.objects()[0]);
+    let computations = &intersection.prepare_computations(ray);
+
+    let colour = world.shade_hit(computations, 0);
+    let expected = single_light * 2.0;
+    assert!(colour.approx_eq(expected));
+  }
+
+  #[test]
+  fn shadow_is_tested_per_light() {
+    let mut world = World::default();
+    world.lights.push(Box::new(PointLight::new(
+      (10.0, -10.0, 10.0),
+      (1.0, 1.0, 1.0),
+    )));
+    let position = Point::new(20.0, -20.0, 20.0);
+
+    assert!(world.lights[0]
+      .intensity_at(position, &world)
+      .approx_eq(0.0));
+    assert!(world.lights[1]
+      .intensity_at(position, &world)
+      .approx_eq(1.0));
+  }
+
+  #[test]
+  fn floor_under_uniform_environment_receives_full_irradiance() {
+    // Samples below the horizon are blocked by the floor, but they'd add nothing anyway
+    let image = ImageBuffer::from_pixel(32, 16, Rgb([1.0f32, 1.0, 1.0]));
+    let map = EnvironmentMap::from_image(&DynamicImage::ImageRgb32F(image)).unwrap();
+    let mut world = World::new();
+    world
+      .lights
+      .push(Box::new(EnvironmentLight::new(map, 4096)));
+    let mut floor = Plane::new();
+    floor.material.ambient = 0.0;
+    floor.material.specular = 0.0;
+    world.add_object(Box::new(floor));
+
+    let ray = Ray::new((0.0, 1.0, 0.0), (0.0, -1.0, 0.0));
+    let intersections = world.intersect(ray);
+    let computations = intersections
+      .hit()
+      .unwrap()
+      .prepare_computations_with(ray, &intersections);
+    let colour = world.direct_lighting(&computations);
+    assert!((colour.red - 0.9).abs() < 0.05, "{colour:?}");
+  }
+
+  #[test]
+  fn shading_intersection_without_lights() {
+    let mut world = World::default();
+    world.lights.clear();
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+
+    let colour = world.colour_at(ray);
+    assert!(colour.approx_eq(Colour::BLACK));
+  }
+
   #[test]
   fn reflected_colour_for_nonreflective_material() {
     let world = {
       let mut world = World::new();
-      world
-        .lights
-        .push(PointLight::new((-10.0, 10.0, -10.0), (1.0, 1.0, 1.0)));
+      world.lights.push(Box::new(PointLight::new(
+        (-10.0, 10.0, -10.0),
+        (1.0, 1.0, 1.0),
+      )));
 
       let mut sphere_1 = Sphere::new();
       sphere_1.material = Material {
@@ -327,19 +528,16 @@ mod tests {
         ..Default::default()
       };
       let mut sphere_2 = Sphere::new();
-      sphere_2.transform = Matrix4x4::scale(0.5, 0.5, 0.5);
+      sphere_2.transform = Transform::new(Matrix4x4::scale(0.5, 0.5, 0.5));
       sphere_2.material.ambient = 1.0;
-      world.objects.push(Box::new(sphere_1));
-      world.objects.push(Box::new(sphere_2));
+      world.add_object(Box::new(sphere_1));
+      world.add_object(Box::new(sphere_2));
 
       world
     };
 
     let ray = Ray::new((0.0, 0.0, 0.0), (0.0, 0.0, 1.0));
-    let intersection = Intersection {
-      t: 1.0,
-      object: &*world.objects[1],
-    };
+    let intersection = Intersection::new(1.0, &*world.objects()[1]);
     let computations = &intersection.prepare_computations(ray);
     let colour = world.reflected_colour(computations, 1);
 
@@ -352,9 +550,9 @@ mod tests {
     let world = {
       let mut world = World::default();
       let mut plane = Plane::new();
-      plane.transform = Matrix4x4::translation(0.0, -1.0, 0.0);
+      plane.transform = Transform::new(Matrix4x4::translation(0.0, -1.0, 0.0));
       plane.material.reflective = 0.5;
-      world.objects.push(Box::new(plane));
+      world.add_object(Box::new(plane));
 
       world
     };
@@ -363,10 +561,7 @@ mod tests {
       (0.0, 0.0, -3.0),
       (0.0, -1.0 / 2.0f32.sqrt(), 1.0 / 2.0f32.sqrt()),
     );
-    let intersection = Intersection {
-      t: 2.0f32.sqrt(),
-      object: &*world.objects[2],
-    };
+    let intersection = Intersection::new(2.0f32.sqrt(), &*world.objects()[2]);
     let computations = &intersection.prepare_computations(ray);
     let colour = world.reflected_colour(computations, 1);
 
@@ -380,9 +575,9 @@ mod tests {
     let world = {
       let mut world = World::default();
       let mut plane = Plane::new();
-      plane.transform = Matrix4x4::translation(0.0, -1.0, 0.0);
+      plane.transform = Transform::new(Matrix4x4::translation(0.0, -1.0, 0.0));
       plane.material.reflective = 0.5;
-      world.objects.push(Box::new(plane));
+      world.add_object(Box::new(plane));
 
       world
     };
@@ -391,10 +586,7 @@ mod tests {
       (0.0, 0.0, -3.0),
       (0.0, -1.0 / 2.0f32.sqrt(), 1.0 / 2.0f32.sqrt()),
     );
-    let intersection = Intersection {
-      t: 2.0f32.sqrt(),
-      object: &*world.objects[2],
-    };
+    let intersection = Intersection::new(2.0f32.sqrt(), &*world.objects()[2]);
     let computations = &intersection.prepare_computations(ray);
     let colour = world.shade_hit(computations, 1);
 
@@ -409,17 +601,17 @@ mod tests {
       let mut world = World::new();
       world
         .lights
-        .push(PointLight::new((0.0, 0.0, 0.0), (1.0, 1.0, 1.0)));
+        .push(Box::new(PointLight::new((0.0, 0.0, 0.0), (1.0, 1.0, 1.0))));
 
       let mut plane_lower = Plane::new();
-      plane_lower.transform = Matrix4x4::translation(0.0, -1.0, 0.0);
+      plane_lower.transform = Transform::new(Matrix4x4::translation(0.0, -1.0, 0.0));
       plane_lower.material.reflective = 1.0;
-      world.objects.push(Box::new(plane_lower));
+      world.add_object(Box::new(plane_lower));
 
       let mut plane_upper = Plane::new();
-      plane_upper.transform = Matrix4x4::translation(0.0, 1.0, 0.0);
+      plane_upper.transform = Transform::new(Matrix4x4::translation(0.0, 1.0, 0.0));
       plane_upper.material.reflective = 1.0;
-      world.objects.push(Box::new(plane_upper));
+      world.add_object(Box::new(plane_upper));
 
       world
     };
@@ -433,9 +625,9 @@ mod tests {
     let world = {
       let mut world = World::default();
       let mut plane = Plane::new();
-      plane.transform = Matrix4x4::translation(0.0, -1.0, 0.0);
+      plane.transform = Transform::new(Matrix4x4::translation(0.0, -1.0, 0.0));
       plane.material.reflective = 0.5;
-      world.objects.push(Box::new(plane));
+      world.add_object(Box::new(plane));
 
       world
     };
@@ -444,14 +636,178 @@ mod tests {
       (0.0, 0.0, -3.0),
       (0.0, -1.0 / 2.0f32.sqrt(), 1.0 / 2.0f32.sqrt()),
     );
-    let intersection = Intersection {
-      t: 2.0f32.sqrt(),
-      object: &*world.objects[2],
-    };
+    let intersection = Intersection::new(2.0f32.sqrt(), &*world.objects()[2]);
     let computations = &intersection.prepare_computations(ray);
     let colour = world.reflected_colour(computations, 0);
 
     let expected = Colour::BLACK;
     assert!(colour.approx_eq(expected));
   }
+
+  #[test]
+  fn refracted_colour_with_opaque_surface() {
+    let world = World::default();
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let intersections = world.intersect(ray);
+    let computations = &intersections[0].prepare_computations_with(ray, &intersections);
+
+    let colour = world.refracted_colour(computations, 5);
+    assert!(colour.approx_eq(Colour::BLACK));
+  }
+
+  #[test]
+  fn refracted_colour_at_maximum_recursion_depth() {
+    let mut world = World::default();
+    let sphere = Sphere {
+      material: Material {
+        transparency: 1.0,
+        refractive_index: 1.5,
+        ..world.objects()[0].material().clone()
+      },
+      ..Sphere::new()
+    };
+    world.objects_mut()[0] = Box::new(sphere);
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 0.0, 1.0));
+    let intersections = world.intersect(ray);
+    let computations = &intersections[0].prepare_computations_with(ray, &intersections);
+
+    let colour = world.refracted_colour(computations, 0);
+    assert!(colour.approx_eq(Colour::BLACK));
+  }
+
+  #[test]
+  fn refracted_colour_under_total_internal_reflection() {
+    let mut world = World::default();
+    let sphere = Sphere {
+      material: Material {
+        transparency: 1.0,
+        refractive_index: 1.5,
+        ..world.objects()[0].material().clone()
+      },
+      ..Sphere::new()
+    };
+    world.objects_mut()[0] = Box::new(sphere);
+    let ray = Ray::new((0.0, 0.0, 1.0 / 2.0f32.sqrt()), (0.0, 1.0, 0.0));
+    let intersections = world.intersect(ray);
+    let computations = &intersections[1].prepare_computations_with(ray, &intersections);
+
+    let colour = world.refracted_colour(computations, 5);
+    assert!(colour.approx_eq(Colour::BLACK));
+  }
+
+  #[test]
+  fn refracted_colour_with_refracted_ray() {
+    let mut world = World::default();
+    let sphere_a = Sphere {
+      material: Material {
+        ambient: 1.0,
+        pattern: Pattern::test_pattern(),
+        ..world.objects()[0].material().clone()
+      },
+      ..Sphere::new()
+    };
+    let sphere_b = Sphere {
+      material: Material {
+        transparency: 1.0,
+        refractive_index: 1.5,
+        ..Default::default()
+      },
+      transform: Transform::new(Matrix4x4::scale(0.5, 0.5, 0.5)),
+    };
+    *world.objects_mut() = vec![Box::new(sphere_a), Box::new(sphere_b)];
+    let ray = Ray::new((0.0, 0.0, 0.1), (0.0, 1.0, 0.0));
+    let intersections = world.intersect(ray);
+    let computations = &intersections[2].prepare_computations_with(ray, &intersections);
+
+    let colour = world.refracted_colour(computations, 5);
+    eprintln!("{colour:?}");
+    let expected = Colour::new(0.0, 0.997365, 0.04760);
+    assert!(colour.approx_eq(expected));
+  }
+
+  #[test]
+  fn shade_hit_with_transparent_material() {
+    let mut world = World::default();
+    let mut floor = Plane::new();
+    floor.transform = Transform::new(Matrix4x4::translation(0.0, -1.0, 0.0));
+    floor.material.transparency = 0.5;
+    floor.material.refractive_index = 1.5;
+    let mut ball = Sphere::new();
+    ball.material.pattern = Pattern::solid(Colour::new(1.0, 0.0, 0.0));
+    ball.material.ambient = 0.5;
+    ball.transform = Transform::new(Matrix4x4::translation(0.0, -3.5, -0.5));
+    world.add_object(Box::new(floor));
+    world.add_object(Box::new(ball));
+
+    let ray = Ray::new(
+      (0.0, 0.0, -3.0),
+      (0.0, -1.0 / 2.0f32.sqrt(), 1.0 / 2.0f32.sqrt()),
+    );
+    let intersections = world.intersect(ray);
+    let computations = &intersections[0].prepare_computations_with(ray, &intersections);
+
+    let colour = world.shade_hit(computations, 5);
+    eprintln!("{colour:?}");
+    let expected = Colour::new(0.93638, 0.68638, 0.68638);
+    assert!(colour.approx_eq(expected));
+  }
+
+  #[test]
+  fn shade_hit_with_reflective_transparent_material() {
+    let mut world = World::default();
+    let mut floor = Plane::new();
+    floor.transform = Transform::new(Matrix4x4::translation(0.0, -1.0, 0.0));
+    floor.material.reflective = 0.5;
+    floor.material.transparency = 0.5;
+    floor.material.refractive_index = 1.5;
+    let mut ball = Sphere::new();
+    ball.material.pattern = Pattern::solid(Colour::new(1.0, 0.0, 0.0));
+    ball.material.ambient = 0.5;
+    ball.transform = Transform::new(Matrix4x4::translation(0.0, -3.5, -0.5));
+    world.add_object(Box::new(floor));
+    world.add_object(Box::new(ball));
+
+    let ray = Ray::new(
+      (0.0, 0.0, -3.0),
+      (0.0, -1.0 / 2.0f32.sqrt(), 1.0 / 2.0f32.sqrt()),
+    );
+    let intersections = world.intersect(ray);
+    let computations = &intersections[0].prepare_computations_with(ray, &intersections);
+
+    let colour = world.shade_hit(computations, 5);
+    eprintln!("{colour:?}");
+    let expected = Colour::new(0.93388, 0.69640, 0.69239);
+    assert!(colour.approx_eq(expected));
+  }
+
+  #[test]
+  fn colour_when_ray_misses_is_background() {
+    let world = World {
+      background: Background::Gradient {
+        bottom: Colour::WHITE,
+        top: Colour::BLUE,
+      },
+      ..Default::default()
+    };
+    let ray = Ray::new((0.0, 0.0, -5.0), (0.0, 1.0, 0.0));
+
+    let colour = world.colour_at(ray);
+    assert!(colour.approx_eq(Colour::BLUE));
+  }
+
+  #[test]
+  fn reflection_of_background() {
+    let mut world = World::new();
+    world.background = Background::Solid(Colour::new(0.2, 0.4, 0.6));
+    let mut plane = Plane::new();
+    plane.material.reflective = 1.0;
+    plane.material.ambient = 0.0;
+    plane.material.diffuse = 0.0;
+    plane.material.specular = 0.0;
+    world.add_object(Box::new(plane));
+
+    let ray = Ray::new((0.0, 1.0, -1.0), (0.0, -1.0, 1.0));
+    let colour = world.colour_at(ray);
+    assert!(colour.approx_eq(Colour::new(0.2, 0.4, 0.6)));
+  }
 }